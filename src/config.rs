@@ -1,5 +1,6 @@
 //! Configuration options.
 
+use std::collections::HashMap;
 use std::fmt::{self, Display, Formatter};
 
 use calloop::LoopHandle;
@@ -35,6 +36,17 @@ pub struct Config {
     pub colors: Colors,
     /// This section documents the `[input]` table.
     pub input: Input,
+    /// This section documents the `[launch]` table.
+    pub launch: Launch,
+    /// This section documents the `[apps."<desktop file ID>"]` tables, like
+    /// `[apps."firefox.desktop"]`.
+    pub apps: Apps,
+    /// Colon-separated desktop names used for `OnlyShowIn`/`NotShowIn`,
+    /// overriding `$XDG_CURRENT_DESKTOP`.
+    pub current_desktop: Option<String>,
+    /// Icon theme name, defaults to the GTK or KDE icon theme and falls back to
+    /// `hicolor`.
+    pub icon_theme: Option<String>,
 }
 
 /// Font configuration.
@@ -63,11 +75,23 @@ pub struct Colors {
     /// Primary background color.
     #[serde(alias = "bg")]
     pub background: Color,
+    /// Symbolic icon color for the `success` style class.
+    pub success: Color,
+    /// Symbolic icon color for the `warning` style class.
+    pub warning: Color,
+    /// Symbolic icon color for the `error` style class.
+    pub error: Color,
 }
 
 impl Default for Colors {
     fn default() -> Self {
-        Self { foreground: Color::new(255, 255, 255), background: Color::new(24, 24, 24) }
+        Self {
+            foreground: Color::new(255, 255, 255),
+            background: Color::new(24, 24, 24),
+            success: Color::new(78, 154, 6),
+            warning: Color::new(245, 121, 0),
+            error: Color::new(204, 0, 0),
+        }
     }
 }
 
@@ -82,14 +106,109 @@ pub struct Input {
     pub velocity_interval: u16,
     /// Percentage of velocity retained each tick.
     pub velocity_friction: f64,
+
+    /// Milliseconds before a touch is considered a long-press.
+    pub long_press_delay: u16,
 }
 
 impl Default for Input {
     fn default() -> Self {
-        Self { velocity_friction: 0.85, max_tap_distance: 400., velocity_interval: 30 }
+        Self {
+            velocity_friction: 0.85,
+            max_tap_distance: 400.,
+            velocity_interval: 30,
+            long_press_delay: 500,
+        }
     }
 }
 
+/// Application launch configuration.
+#[derive(Docgen, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Launch {
+    /// Terminal command for `Terminal=true` applications, including the flag
+    /// used to execute a command.
+    pub terminal: String,
+    /// Command prepended to every application's command line, like
+    /// `gamemoderun` or `firejail`.
+    pub prefix: String,
+    /// Environment variables for all applications.
+    pub env: Env,
+    /// Launch applications in their own transient systemd user scope.
+    pub systemd_scope: bool,
+}
+
+impl Default for Launch {
+    fn default() -> Self {
+        Self {
+            terminal: String::from("foot -e"),
+            systemd_scope: false,
+            prefix: Default::default(),
+            env: Default::default(),
+        }
+    }
+}
+
+/// Environment variables, keyed by name.
+#[derive(Deserialize, Default, PartialEq, Eq, Debug)]
+#[serde(transparent)]
+pub struct Env(HashMap<String, String>);
+
+impl Env {
+    /// Iterate over all variable names and values.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter()
+    }
+}
+
+impl Docgen for Env {
+    fn doc_type() -> DocType {
+        DocType::Leaf(Leaf::new("table"))
+    }
+
+    fn format(&self) -> String {
+        String::from("{}")
+    }
+}
+
+/// Per-application overrides, keyed by desktop file ID.
+#[derive(Deserialize, Default, PartialEq, Eq, Debug)]
+#[serde(transparent)]
+pub struct Apps(HashMap<String, App>);
+
+impl Apps {
+    /// Get the overrides for a desktop file ID.
+    pub fn get(&self, id: &str) -> Option<&App> {
+        self.0.get(id)
+    }
+}
+
+impl Docgen for Apps {
+    fn doc_type() -> DocType {
+        App::doc_type()
+    }
+
+    fn format(&self) -> String {
+        String::new()
+    }
+}
+
+/// Application overrides.
+#[derive(Docgen, Deserialize, Default, PartialEq, Eq, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct App {
+    /// Application name.
+    pub name: Option<String>,
+    /// Icon name or absolute path.
+    pub icon: Option<String>,
+    /// Hide the application, overriding its `NoDisplay` key.
+    pub hidden: Option<bool>,
+    /// Command prefix, overriding the `[launch]` table's prefix.
+    pub prefix: Option<String>,
+    /// Environment variables, in addition to the `[launch]` table's variables.
+    pub env: Env,
+}
+
 /// RGB color.
 #[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
 pub struct Color {
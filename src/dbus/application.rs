@@ -0,0 +1,26 @@
+//! # DBus interface proxy for: `org.freedesktop.Application`
+//!
+//! This code was generated by `zbus-xmlgen` `3.1.0` from DBus introspection
+//! data. Source: `Interface '/org/gnome/Nautilus' from service
+//! 'org.gnome.Nautilus' on session bus`.
+//!
+//! The interface is specified by the freedesktop desktop entry specification.
+
+use zbus::proxy;
+
+#[proxy(interface = "org.freedesktop.Application")]
+pub trait Application {
+    /// Activate method
+    fn activate(
+        &self,
+        platform_data: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    /// ActivateAction method
+    fn activate_action(
+        &self,
+        action_name: &str,
+        parameter: &[zbus::zvariant::Value<'_>],
+        platform_data: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<()>;
+}
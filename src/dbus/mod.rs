@@ -1,14 +1,24 @@
 //! DBus system interfaces.
 
+use std::collections::HashMap;
 use std::error::Error;
+use std::fmt::Write;
+use std::hash::{BuildHasher, RandomState};
 
 use tokio::runtime::Builder;
 use zbus::Connection;
+use zbus::zvariant::{OwnedObjectPath, Value};
 
+use crate::dbus::application::ApplicationProxy;
 use crate::dbus::logind::ManagerProxy;
+use crate::dbus::systemd::ManagerProxy as SystemdManagerProxy;
 
+#[allow(clippy::all)]
+mod application;
 #[allow(clippy::all)]
 mod logind;
+#[allow(clippy::all)]
+mod systemd;
 
 /// Shutdown the system.
 pub fn shutdown() -> Result<(), Box<dyn Error>> {
@@ -35,3 +45,276 @@ async fn reboot_async() -> zbus::Result<()> {
     let logind = ManagerProxy::new(&connection).await?;
     logind.reboot(false).await
 }
+
+/// Launch a `DBusActivatable` application.
+///
+/// The `app_id` is the application's desktop file ID, without the `.desktop`
+/// suffix. If an `action` is specified, the matching desktop action is
+/// activated instead of the application itself.
+///
+/// The xdg-activation `token` allows the application to focus its window.
+pub fn activate_app(
+    app_id: &str,
+    action: Option<&str>,
+    token: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(activate_app_async(app_id, action, token))?;
+    Ok(())
+}
+
+/// Async handler for the application activation.
+async fn activate_app_async(
+    app_id: &str,
+    action: Option<&str>,
+    token: Option<&str>,
+) -> zbus::Result<()> {
+    let connection = Connection::session().await?;
+    activate(&connection, app_id, action, token).await
+}
+
+/// Activate an application through its `org.freedesktop.Application`
+/// interface.
+///
+/// The bus will start the application if it is not running already.
+async fn activate(
+    connection: &Connection,
+    app_id: &str,
+    action: Option<&str>,
+    token: Option<&str>,
+) -> zbus::Result<()> {
+    let application = ApplicationProxy::new(connection, app_id, object_path(app_id)).await?;
+
+    // Pass the token using both its Wayland and legacy X11 startup notification
+    // keys.
+    let mut platform_data = HashMap::new();
+    if let Some(token) = token {
+        platform_data.insert("activation-token", Value::from(token));
+        platform_data.insert("desktop-startup-id", Value::from(token));
+    }
+
+    match action {
+        Some(action) => application.activate_action(action, &[], platform_data).await,
+        None => application.activate(platform_data).await,
+    }
+}
+
+/// Get the object path of an application's `org.freedesktop.Application`
+/// interface.
+///
+/// The path is derived from the application ID by replacing all `.` with `/`
+/// and all `-` with `_`.
+fn object_path(app_id: &str) -> String {
+    format!("/{}", app_id.replace('.', "/").replace('-', "_"))
+}
+
+/// Move a launched application into a new transient systemd user scope.
+///
+/// The `app_id` is the application's desktop file ID, without the `.desktop`
+/// suffix.
+pub fn start_app_scope(app_id: &str, pid: u32) -> Result<(), Box<dyn Error>> {
+    Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(start_app_scope_async(app_id, pid))?;
+    Ok(())
+}
+
+/// Async handler for the systemd scope creation.
+async fn start_app_scope_async(app_id: &str, pid: u32) -> zbus::Result<()> {
+    let connection = Connection::session().await?;
+    start_transient_scope(&connection, app_id, pid).await?;
+    Ok(())
+}
+
+/// Start a transient systemd scope containing a process.
+async fn start_transient_scope(
+    connection: &Connection,
+    app_id: &str,
+    pid: u32,
+) -> zbus::Result<OwnedObjectPath> {
+    let systemd = SystemdManagerProxy::new(connection).await?;
+
+    let properties = [
+        ("PIDs", Value::from(vec![pid])),
+        // Clean up the scope even if the application failed.
+        ("CollectMode", Value::from("inactive-or-failed")),
+    ];
+    systemd.start_transient_unit(&scope_name(app_id), "fail", &properties, &[]).await
+}
+
+/// Get a unique scope name for an application.
+///
+/// This follows systemd's `app-<launcher>-<ApplicationID>-<RANDOM>.scope`
+/// naming convention for desktop applications.
+fn scope_name(app_id: &str) -> String {
+    let random = RandomState::new().hash_one(app_id);
+    format!("app-tzompantli-{}-{random:x}.scope", escape_unit_name(app_id))
+}
+
+/// Escape a string for use in a systemd unit name.
+///
+/// Like `systemd-escape`, all bytes except ASCII alphanumerics, `:`, `_`, and
+/// non-leading `.` are escaped. This also ensures the escaped string does not
+/// contain any `-` separators.
+fn escape_unit_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for (i, byte) in name.bytes().enumerate() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' => escaped.push(byte as char),
+            b'.' if i > 0 => escaped.push('.'),
+            _ => {
+                let _ = write!(escaped, "\\x{byte:02x}");
+            },
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::net::UnixStream;
+    use zbus::object_server::Interface;
+    use zbus::zvariant::OwnedValue;
+    use zbus::{Guid, connection, interface};
+
+    use super::*;
+
+    /// Transient unit creation request.
+    type Unit = (String, String, Vec<(String, OwnedValue)>);
+
+    /// Mock systemd manager, recording all created units.
+    #[derive(Clone, Default)]
+    struct MockSystemd {
+        units: Arc<Mutex<Vec<Unit>>>,
+    }
+
+    #[interface(name = "org.freedesktop.systemd1.Manager")]
+    impl MockSystemd {
+        fn start_transient_unit(
+            &self,
+            name: String,
+            mode: String,
+            properties: Vec<(String, OwnedValue)>,
+            _aux: Vec<(String, Vec<(String, OwnedValue)>)>,
+        ) -> OwnedObjectPath {
+            self.units.lock().unwrap().push((name, mode, properties));
+            OwnedObjectPath::try_from("/org/freedesktop/systemd1/job/1").unwrap()
+        }
+    }
+
+    #[test]
+    fn transient_scope() {
+        let systemd = MockSystemd::default();
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let job = runtime.block_on(async {
+            let (client, _server) =
+                p2p_connection("/org/freedesktop/systemd1", systemd.clone()).await;
+            start_transient_scope(&client, "org.example.App-beta", 1234).await.unwrap()
+        });
+        assert_eq!(job.as_str(), "/org/freedesktop/systemd1/job/1");
+
+        let units = systemd.units.lock().unwrap();
+        assert_eq!(units.len(), 1);
+
+        let (name, mode, properties) = &units[0];
+        assert!(name.starts_with("app-tzompantli-org.example.App\\x2dbeta-"), "{name}");
+        assert!(name.ends_with(".scope"));
+        assert_eq!(mode, "fail");
+
+        let pids = properties.iter().find(|(key, _)| key == "PIDs").unwrap();
+        assert_eq!(Vec::<u32>::try_from(pids.1.try_clone().unwrap()).unwrap(), [1234]);
+    }
+
+    /// Application activation, with its action and activation token.
+    type Activation = (Option<String>, Option<String>);
+
+    /// Mock application, recording all activations.
+    #[derive(Clone, Default)]
+    struct MockApplication {
+        activations: Arc<Mutex<Vec<Activation>>>,
+    }
+
+    #[interface(name = "org.freedesktop.Application")]
+    impl MockApplication {
+        fn activate(&self, platform_data: HashMap<String, OwnedValue>) {
+            self.activations.lock().unwrap().push((None, token(&platform_data)));
+        }
+
+        fn activate_action(
+            &self,
+            action_name: String,
+            _parameter: Vec<OwnedValue>,
+            platform_data: HashMap<String, OwnedValue>,
+        ) {
+            self.activations.lock().unwrap().push((Some(action_name), token(&platform_data)));
+        }
+    }
+
+    /// Get the activation token from an application's platform data.
+    fn token(platform_data: &HashMap<String, OwnedValue>) -> Option<String> {
+        let token = platform_data.get("activation-token")?;
+        String::try_from(token.try_clone().unwrap()).ok()
+    }
+
+    #[test]
+    fn activate_application() {
+        let application = MockApplication::default();
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let (client, _server) =
+                p2p_connection("/org/example/My_App", application.clone()).await;
+
+            activate(&client, "org.example.My-App", None, None).await.unwrap();
+            activate(&client, "org.example.My-App", Some("new-window"), Some("token"))
+                .await
+                .unwrap();
+
+            // Activation fails if the application does not implement the interface.
+            assert!(activate(&client, "org.example.Other", None, None).await.is_err());
+        });
+
+        let activations = application.activations.lock().unwrap();
+        assert_eq!(*activations, [(None, None), (Some("new-window".into()), Some("token".into()))]);
+    }
+
+    #[test]
+    fn application_object_path() {
+        assert_eq!(object_path("org.gnome.Nautilus"), "/org/gnome/Nautilus");
+        assert_eq!(object_path("org.example.My-App"), "/org/example/My_App");
+    }
+
+    #[test]
+    fn unit_name_escaping() {
+        assert_eq!(escape_unit_name("org.gnome.Settings"), "org.gnome.Settings");
+        assert_eq!(escape_unit_name("kde4-app"), "kde4\\x2dapp");
+        assert_eq!(escape_unit_name(".hidden app"), "\\x2ehidden\\x20app");
+        assert_eq!(escape_unit_name("caf\u{e9}"), "caf\\xc3\\xa9");
+    }
+
+    /// Connect to a mock service over a private peer-to-peer bus.
+    ///
+    /// Returns the client and server connections.
+    async fn p2p_connection<I: Interface>(
+        path: &'static str,
+        iface: I,
+    ) -> (Connection, Connection) {
+        let (client, server) = UnixStream::pair().unwrap();
+        let server = connection::Builder::unix_stream(server)
+            .server(Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at(path, iface)
+            .unwrap()
+            .build();
+        let server = tokio::spawn(server);
+        let client = connection::Builder::unix_stream(client).p2p().build().await.unwrap();
+        (client, server.await.unwrap().unwrap())
+    }
+}
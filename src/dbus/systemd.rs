@@ -0,0 +1,25 @@
+//! # DBus interface proxy for: `org.freedesktop.systemd1.Manager`
+//!
+//! This code was generated by `zbus-xmlgen` `3.1.0` from DBus introspection
+//! data. Source: `Interface '/org/freedesktop/systemd1' from service
+//! 'org.freedesktop.systemd1' on session bus`.
+//!
+//! Only the methods required for launching applications were retained.
+
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+pub trait Manager {
+    /// StartTransientUnit method
+    fn start_transient_unit(
+        &self,
+        name: &str,
+        mode: &str,
+        properties: &[(&str, zbus::zvariant::Value<'_>)],
+        aux: &[(&str, &[(&str, zbus::zvariant::Value<'_>)])],
+    ) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+}
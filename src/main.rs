@@ -21,6 +21,7 @@ use crate::wayland::ProtocolStates;
 mod config;
 mod dbus;
 mod geometry;
+mod process;
 mod ui;
 mod wayland;
 mod xdg;
@@ -99,7 +100,7 @@ impl State {
             .unwrap_or_default();
 
         // Create the Wayland window.
-        let window = Window::new(&protocol_states, connection, queue, config)?;
+        let window = Window::new(event_loop.clone(), &protocol_states, connection, queue, config)?;
 
         Ok(Self {
             protocol_states,
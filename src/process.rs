@@ -0,0 +1,160 @@
+//! Detached application processes.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::net::Shutdown;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+use xdg::BaseDirectories;
+
+/// Journal socket for stream based logging.
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/stdout";
+
+/// Time launched applications have to fail during their startup.
+const STARTUP_GRACE_PERIOD: Duration = Duration::from_millis(250);
+
+/// Interval for checking the application's status during startup.
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Syslog priority for `stdout`.
+const PRIORITY_INFO: u8 = 6;
+
+/// Syslog priority for `stderr`.
+const PRIORITY_ERR: u8 = 3;
+
+/// Spawn an application in its own session.
+///
+/// The application's output is sent to the journal using `identifier`,
+/// falling back to `launch.log` in the state directory.
+pub fn spawn(command: &mut Command, identifier: &str) -> io::Result<Child> {
+    let stdout = output(identifier, PRIORITY_INFO);
+    let stderr = output(identifier, PRIORITY_ERR);
+    spawn_with_output(command, stdout, stderr)
+}
+
+/// Spawn an application in its own session, with the specified output streams.
+///
+/// Failure to execute the binary is reported immediately, since the standard
+/// library reports `exec` errors of the forked child through a CLOEXEC pipe.
+/// The child does not need to be double-forked, since it is reparented to init
+/// once the launcher exits.
+fn spawn_with_output(command: &mut Command, stdout: Stdio, stderr: Stdio) -> io::Result<Child> {
+    command.stdin(Stdio::null());
+    command.stdout(stdout);
+    command.stderr(stderr);
+
+    // Detach from the launcher's session and process group.
+    unsafe {
+        command.pre_exec(|| match libc::setsid() {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        });
+    }
+
+    command.spawn()
+}
+
+/// Wait for the application's startup grace period.
+///
+/// Returns an error if the application exited unsuccessfully before the grace
+/// period has elapsed.
+pub fn wait_startup(child: &mut Child) -> io::Result<()> {
+    let start = Instant::now();
+    while start.elapsed() < STARTUP_GRACE_PERIOD {
+        match child.try_wait()? {
+            Some(status) if !status.success() => {
+                return Err(io::Error::other(format!("exited during startup ({status})")));
+            },
+            Some(_) => break,
+            None => thread::sleep(STARTUP_POLL_INTERVAL),
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the output stream for a launched application.
+fn output(identifier: &str, priority: u8) -> Stdio {
+    match journal_stream(identifier, priority).or_else(|_| log_file()) {
+        Ok(fd) => fd.into(),
+        Err(err) => {
+            warn!("Failed to open application log: {err}");
+            Stdio::null()
+        },
+    }
+}
+
+/// Connect to the journal's stream socket.
+///
+/// This is equivalent to `systemd-cat`, logging every line of output with the
+/// same syslog priority.
+fn journal_stream(identifier: &str, priority: u8) -> io::Result<OwnedFd> {
+    let mut stream = UnixStream::connect(JOURNAL_SOCKET)?;
+    stream.shutdown(Shutdown::Read)?;
+
+    // Write the header of identifier, unit ID, priority, level prefix parsing and
+    // forwarding to syslog, kmsg, and the console.
+    write!(stream, "{identifier}\n\n{priority}\n0\n0\n0\n0\n")?;
+
+    Ok(stream.into())
+}
+
+/// Open the launch log file, creating it if necessary.
+fn log_file() -> io::Result<OwnedFd> {
+    let path = BaseDirectories::with_prefix("tzompantli").place_state_file("launch.log")?;
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(file.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// Spawn an application without writing to the journal or launch log.
+    fn spawn_quiet(command: &mut Command) -> io::Result<Child> {
+        spawn_with_output(command, Stdio::null(), Stdio::null())
+    }
+
+    #[test]
+    fn own_session() {
+        let mut child = spawn_quiet(Command::new("sleep").arg("1")).unwrap();
+
+        // The session ID is the 6th field of `/proc/<pid>/stat`.
+        let stat = fs::read_to_string(format!("/proc/{}/stat", child.id())).unwrap();
+        let (_, fields) = stat.rsplit_once(')').unwrap();
+        let session = fields.split_whitespace().nth(3).unwrap();
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert_eq!(session, child.id().to_string());
+    }
+
+    #[test]
+    fn exec_failure() {
+        let err = spawn_quiet(&mut Command::new("/nonexistent/binary")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn startup_failure() {
+        let mut child = spawn_quiet(Command::new("sh").args(["-c", "exit 3"])).unwrap();
+        assert!(wait_startup(&mut child).is_err());
+
+        let mut child = spawn_quiet(&mut Command::new("true")).unwrap();
+        assert!(wait_startup(&mut child).is_ok());
+
+        let mut child = spawn_quiet(Command::new("sleep").arg("1")).unwrap();
+        assert!(wait_startup(&mut child).is_ok());
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
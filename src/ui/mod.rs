@@ -1,6 +1,7 @@
 mod renderer;
 mod skia;
 pub mod window;
+mod xpm;
 
 use std::time::Instant;
 
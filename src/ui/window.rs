@@ -1,37 +1,46 @@
 //! Wayland window rendering.
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::process::Command;
 use std::ptr::NonNull;
 use std::sync::Arc;
-use std::{mem, process};
+use std::time::Duration;
+use std::{io, mem, process};
 
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{LoopHandle, RegistrationToken};
 use glutin::display::{Display, DisplayApiPreference};
 use raw_window_handle::{RawDisplayHandle, WaylandDisplayHandle};
 use rayon::prelude::*;
 use resvg::tiny_skia::Pixmap as SvgPixmap;
-use resvg::usvg::{Options as SvgOptions, Transform as SvgTransform, Tree as SvgTree};
+use resvg::usvg::{
+    Options as SvgOptions, Transform as SvgTransform, Tree as SvgTree, decompress_svgz,
+};
 use skia_safe::image::images;
 use skia_safe::textlayout::{
     FontCollection, ParagraphBuilder, ParagraphStyle, TextAlign, TextStyle,
 };
 use skia_safe::{
     AlphaType, Canvas as SkiaCanvas, ColorType, Data, FilterMode, FontMgr, IRect, Image, ImageInfo,
-    MipmapMode, Paint, Rect, SamplingOptions,
+    MipmapMode, Paint, PaintStyle, Rect, SamplingOptions,
 };
+use smithay_client_toolkit::activation::ActivationState;
 use smithay_client_toolkit::compositor::{CompositorState, Region};
+use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
 use smithay_client_toolkit::reexports::client::{Connection, QueueHandle};
 use smithay_client_toolkit::reexports::protocols::wp::viewporter::client::wp_viewport::WpViewport;
 use smithay_client_toolkit::shell::WaylandSurface;
 use smithay_client_toolkit::shell::xdg::window::{Window as XdgWindow, WindowDecorations};
 use tracing::error;
 
-use crate::config::Config;
+use crate::config::{Colors, Config};
 use crate::geometry::{Point, Size};
 use crate::ui::ScrollVelocity;
 use crate::ui::renderer::Renderer;
 use crate::ui::skia::Canvas;
-use crate::wayland::ProtocolStates;
+use crate::ui::xpm::Xpm;
+use crate::wayland::{ActivationRequest, ProtocolStates};
 use crate::xdg::{DesktopEntries, DesktopEntry, ExecAction, Icon, IconType};
 use crate::{Error, State, dbus};
 
@@ -47,6 +56,15 @@ const MIN_PADDING: f64 = 8.;
 /// Desktop entry icon size at scale 1.
 const ICON_SIZE: f64 = 64.;
 
+/// Maximum width of the quick action menu at scale 1.
+const ACTION_MENU_WIDTH: f64 = 320.;
+
+/// Height of a quick action menu entry at scale 1.
+const ACTION_HEIGHT: f64 = 56.;
+
+/// Quick action icon size at scale 1.
+const ACTION_ICON_SIZE: f64 = 32.;
+
 /// Wayland window.
 pub struct Window {
     pub queue: QueueHandle<State>,
@@ -60,6 +78,11 @@ pub struct Window {
     touch_state: TouchState,
     scroll_offset: f64,
 
+    event_loop: LoopHandle<'static, State>,
+    long_press_timer: Option<RegistrationToken>,
+    action_menu: Option<ActionMenu>,
+    launch_error: Option<String>,
+
     connection: Connection,
     xdg_window: XdgWindow,
     viewport: WpViewport,
@@ -76,6 +99,7 @@ pub struct Window {
 
 impl Window {
     pub fn new(
+        event_loop: LoopHandle<'static, State>,
         protocol_states: &ProtocolStates,
         connection: Connection,
         queue: QueueHandle<State>,
@@ -111,12 +135,16 @@ impl Window {
         let size = Size { width: 360, height: 720 };
 
         // Lookup available applications.
-        let entries = DesktopEntries::new().expect("Unable to load desktop entries");
+        let icon_theme = config.icon_theme.as_deref();
+        let mut entries = DesktopEntries::new(icon_theme).expect("Unable to load desktop entries");
+        entries.set_current_desktop(config.current_desktop.as_deref());
+        entries.set_overrides(&config.apps);
 
         let render_cache = RenderCache::new(&config);
 
         Ok(Self {
             render_cache,
+            event_loop,
             connection,
             xdg_window,
             viewport,
@@ -134,6 +162,9 @@ impl Window {
             touch_state: Default::default(),
             velocity: Default::default(),
             canvas: Default::default(),
+            long_press_timer: Default::default(),
+            action_menu: Default::default(),
+            launch_error: Default::default(),
         })
     }
 
@@ -196,6 +227,31 @@ impl Window {
                 for entry in entries {
                     Self::draw_entry(canvas, &mut self.render_cache, grid.entry_size, entry);
                 }
+
+                // Render quick action menu above all entries.
+                if let Some(menu) = &self.action_menu {
+                    Self::draw_action_menu(
+                        canvas,
+                        &mut self.render_cache,
+                        &self.entries,
+                        &self.config.colors,
+                        menu,
+                        physical_size,
+                        self.scale,
+                    );
+                }
+
+                // Render launch errors above everything else.
+                if let Some(launch_error) = &self.launch_error {
+                    Self::draw_launch_error(
+                        canvas,
+                        &self.render_cache,
+                        &self.config.colors,
+                        launch_error,
+                        physical_size,
+                        self.scale,
+                    );
+                }
             });
         });
 
@@ -254,6 +310,107 @@ impl Window {
         }
     }
 
+    /// Draw the quick action menu overlay.
+    fn draw_action_menu(
+        canvas: &SkiaCanvas,
+        render_cache: &mut RenderCache,
+        entries: &DesktopEntries,
+        colors: &Colors,
+        menu: &ActionMenu,
+        size: Size,
+        scale: f64,
+    ) {
+        let entry = match entries.all().get(menu.entry) {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        // Dim the desktop entries below the menu.
+        let mut paint = Paint::default();
+        let mut background = colors.background.as_color4f();
+        background.a = 0.85;
+        paint.set_color4f(background, None);
+        canvas.draw_rect(Rect::from_wh(size.width as f32, size.height as f32), &paint);
+
+        // Draw menu background and border.
+        let menu_rect = menu.rect(size, scale);
+        paint.set_color4f(colors.background.as_color4f(), None);
+        canvas.draw_rect(menu_rect, &paint);
+        paint.set_color4f(colors.foreground.as_color4f(), None);
+        paint.set_stroke_width(scale as f32);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_anti_alias(true);
+        canvas.draw_rect(menu_rect, &paint);
+
+        let icon_size = (ACTION_ICON_SIZE * scale).round() as f32;
+        let icon_scale = scale.ceil() as u32;
+        for (i, action) in entry.actions.iter().enumerate() {
+            let rect = menu.action_rect(size, scale, i);
+            let padding = (rect.height() - icon_size) / 2.;
+
+            // Draw the action's icon, rendering it only once.
+            let icon = entries.action_icon(entry, action, ACTION_ICON_SIZE as u32, icon_scale);
+            let cache_key = (icon, icon_size as u32);
+            let rendered =
+                render_cache.rendered_actions.entry(cache_key).or_insert_with_key(|(icon, _)| {
+                    let name = action.name.clone();
+                    RenderCache::render_icon(
+                        Point::default(),
+                        name,
+                        icon.clone(),
+                        icon_size,
+                        colors,
+                    )
+                });
+            if let Some(rendered) = rendered {
+                let image_rect = Rect::from_xywh(
+                    rect.left + padding + rendered.image_point.x,
+                    rect.top + padding + rendered.image_point.y,
+                    rendered.image_size.width as f32,
+                    rendered.image_size.height as f32,
+                );
+                canvas.draw_image_rect(&rendered.image, None, image_rect, &render_cache.png_paint);
+            }
+
+            // Draw the action's name next to its icon.
+            let mut builder =
+                ParagraphBuilder::new(&render_cache.paragraph_style, &render_cache.font_collection);
+            builder.add_text(&*action.name);
+            let mut paragraph = builder.build();
+            let text_x = rect.left + icon_size + 2. * padding;
+            paragraph.layout(rect.right - padding - text_x);
+            let text_y = rect.top + (rect.height() - paragraph.height()) / 2.;
+            paragraph.paint(canvas, Point::new(text_x, text_y));
+        }
+    }
+
+    /// Draw a launch error message at the bottom of the window.
+    fn draw_launch_error(
+        canvas: &SkiaCanvas,
+        render_cache: &RenderCache,
+        colors: &Colors,
+        message: &str,
+        size: Size,
+        scale: f64,
+    ) {
+        let padding = (MIN_PADDING * scale) as f32;
+
+        let mut builder =
+            ParagraphBuilder::new(&render_cache.paragraph_style, &render_cache.font_collection);
+        builder.add_text(message);
+        let mut paragraph = builder.build();
+        paragraph.layout(size.width as f32 - 2. * padding);
+
+        // Draw background with the error color.
+        let height = paragraph.height() + 2. * padding;
+        let rect = Rect::from_xywh(0., size.height as f32 - height, size.width as f32, height);
+        let mut paint = Paint::default();
+        paint.set_color4f(colors.error.as_color4f(), None);
+        canvas.draw_rect(rect, &paint);
+
+        paragraph.paint(canvas, Point::new(padding, rect.top + padding));
+    }
+
     /// Unstall the renderer.
     ///
     /// This will render a new frame if there currently is no frame request
@@ -308,7 +465,29 @@ impl Window {
 
     /// Handle config updates.
     pub fn update_config(&mut self, config: Config) {
-        let dirty = self.render_cache.update_config(self.scale, &config);
+        let mut dirty = self.render_cache.update_config(self.scale, &config);
+
+        // Update filtering of desktop environment specific entries.
+        if config.current_desktop != self.config.current_desktop {
+            self.entries.set_current_desktop(config.current_desktop.as_deref());
+            dirty = true;
+        }
+
+        // Update application names, icons, and visibility.
+        if config.apps != self.config.apps {
+            self.entries.set_overrides(&config.apps);
+            dirty = true;
+
+            // Close the action menu, since overrides can reorder the entries.
+            self.action_menu = None;
+        }
+
+        // Reload all icons with the new theme.
+        if config.icon_theme != self.config.icon_theme {
+            self.entries.set_icon_theme(config.icon_theme.as_deref());
+            self.render_cache.clear();
+            dirty = true;
+        }
 
         self.config = config;
         self.dirty |= dirty;
@@ -322,14 +501,33 @@ impl Window {
     pub fn touch_down(&mut self, logical_point: Point<f64>) {
         // Cancel velocity when a new touch sequence starts.
         self.velocity.set(0.);
+        self.cancel_long_press();
+
+        // Dismiss launch errors on the next touch.
+        if self.launch_error.take().is_some() {
+            self.dirty = true;
+            self.unstall();
+        }
 
         // Convert position to physical space.
         let point = logical_point * self.scale;
         self.touch_state.point = point;
         self.touch_state.start = point;
 
+        // Handle taps on the quick action menu.
+        if let Some(menu) = &self.action_menu {
+            let index = menu.index_at(self.size * self.scale, self.scale, point);
+            self.touch_state.action = index.map_or(TouchAction::None, TouchAction::ActionTap);
+            return;
+        }
+
         if let Some(index) = self.entry_at(point) {
             self.touch_state.action = TouchAction::Tap(index);
+
+            // Open quick actions if the touch is held in place.
+            if !self.configuring {
+                self.start_long_press();
+            }
         } else {
             self.touch_state.action = TouchAction::None;
         }
@@ -348,6 +546,12 @@ impl Window {
             return;
         }
         self.touch_state.action = TouchAction::Drag;
+        self.cancel_long_press();
+
+        // Prevent scrolling while the quick action menu is open.
+        if self.action_menu.is_some() {
+            return;
+        }
 
         // Calculate current scroll velocity.
         let delta = self.touch_state.point.y - old_point.y;
@@ -365,7 +569,42 @@ impl Window {
     }
 
     /// Handle touch release.
-    pub fn touch_up(&mut self) {
+    ///
+    /// The seat and serial of the tap are used to request an activation token
+    /// for launched applications.
+    pub fn touch_up(
+        &mut self,
+        activation: Option<&ActivationState>,
+        seat_serial: Option<(WlSeat, u32)>,
+    ) {
+        self.cancel_long_press();
+
+        // Handle quick action menu interactions.
+        if let Some(menu) = &self.action_menu {
+            match self.touch_state.action {
+                TouchAction::ActionTap(index) => {
+                    let entry = self.entries.all().get(menu.entry);
+                    let action = entry.and_then(|entry| Some((entry, entry.actions.get(index)?)));
+                    if let Some((entry, action)) = action {
+                        let launch = Launch {
+                            action: Some(action.id.clone()),
+                            ..Launch::new(entry, action.exec.as_deref())
+                        };
+                        self.request_launch(launch, activation, seat_serial);
+                    }
+                },
+                // Close the menu when tapping outside of it.
+                TouchAction::None => (),
+                // Ignore release of the long-press which opened the menu.
+                _ => return,
+            }
+
+            self.action_menu = None;
+            self.dirty = true;
+            self.unstall();
+            return;
+        }
+
         let index = match &self.touch_state.action {
             TouchAction::Tap(index) => index,
             _ => return,
@@ -398,7 +637,12 @@ impl Window {
                     error!("Reboot failed: {err}");
                 }
             },
-            ExecAction::Run(_) if self.configuring => {
+            ExecAction::Run(_) | ExecAction::OpenUrl(_) if self.configuring => {
+                // Ignore entries whose visibility isn't controlled by `NoDisplay`.
+                if !entry.toggleable() {
+                    return;
+                }
+
                 // Toggle status of the desktop entry.
                 let entry = self.entries.all_get_mut(*index).unwrap();
                 if let Err(err) = entry.toggle_hidden() {
@@ -411,16 +655,180 @@ impl Window {
                 self.dirty = true;
                 self.unstall();
             },
-            ExecAction::Run(exec) => {
-                let cmd = exec.split(' ').collect::<Vec<_>>();
-                match Command::new(cmd[0]).args(&cmd[1..]).spawn() {
-                    Ok(_) => process::exit(0),
-                    Err(err) => error!("Process launch failed: {err}"),
-                }
+            ExecAction::Run(args) => {
+                let launch = Launch::new(entry, args.as_deref());
+                self.request_launch(launch, activation, seat_serial);
+            },
+            ExecAction::OpenUrl(url) => {
+                let args = ["xdg-open".into(), url.clone()];
+                let launch = Launch {
+                    dbus_app_id: None,
+                    terminal: false,
+                    ..Launch::new(entry, Some(&args[..]))
+                };
+                self.request_launch(launch, activation, seat_serial);
             },
         }
     }
 
+    /// Request an activation token for launching an application.
+    ///
+    /// The application is launched immediately if the compositor does not
+    /// support xdg-activation.
+    fn request_launch(
+        &mut self,
+        launch: Launch,
+        activation: Option<&ActivationState>,
+        seat_serial: Option<(WlSeat, u32)>,
+    ) {
+        match activation {
+            Some(activation) => {
+                let surface = Some(self.xdg_window.wl_surface().clone());
+                let request = ActivationRequest { launch, seat_serial, surface };
+                activation.request_token_with_data(&self.queue, request);
+            },
+            None => self.launch(&launch, None),
+        }
+    }
+
+    /// Launch an application and exit.
+    ///
+    /// The activation `token` is passed on to the application, allowing it to
+    /// focus its window.
+    ///
+    /// If the launch fails, the window stays open and shows the error instead.
+    pub fn launch(&mut self, launch: &Launch, token: Option<&str>) {
+        // Prefer DBus activation, falling back to `Exec` on failure.
+        if let Some(app_id) = &launch.dbus_app_id {
+            match dbus::activate_app(app_id, launch.action.as_deref(), token) {
+                Ok(()) => process::exit(0),
+                Err(err) => error!("DBus activation of {app_id:?} failed: {err}"),
+            }
+        }
+
+        // Applications without `Exec` can only be launched over DBus.
+        let args = match &launch.args {
+            Some(args) => args,
+            None => {
+                let err = io::Error::new(io::ErrorKind::Unsupported, "missing Exec key");
+                self.show_launch_error(launch, err);
+                return;
+            },
+        };
+
+        let app = launch.desktop_id.as_deref().and_then(|id| self.config.apps.get(id));
+
+        // Run terminal applications inside the terminal emulator.
+        let mut argv = Vec::new();
+        if launch.terminal {
+            argv.extend(self.config.launch.terminal.split_whitespace());
+        }
+
+        // Add the per-application or global command prefix.
+        let prefix = app.and_then(|app| app.prefix.as_ref()).unwrap_or(&self.config.launch.prefix);
+        argv.extend(prefix.split_whitespace());
+
+        argv.extend(args.iter().map(String::as_str));
+
+        let mut command = Command::new(argv[0]);
+        command.args(&argv[1..]);
+
+        if let Some(working_directory) = &launch.working_directory {
+            command.current_dir(working_directory);
+        }
+
+        // Request the discrete GPU on hybrid graphics systems.
+        if launch.prefers_non_default_gpu {
+            command.env("DRI_PRIME", "1");
+        }
+
+        // Apply configured environment variables, preferring per-application values.
+        command.envs(self.config.launch.env.iter());
+        if let Some(app) = app {
+            command.envs(app.env.iter());
+        }
+
+        // Replace activation tokens inherited from our own environment.
+        command.env_remove("XDG_ACTIVATION_TOKEN").env_remove("DESKTOP_STARTUP_ID");
+        if let Some(token) = token {
+            command.env("XDG_ACTIVATION_TOKEN", token);
+            if launch.startup_notify {
+                command.env("DESKTOP_STARTUP_ID", token);
+            }
+        }
+
+        let app_id = launch.app_id.as_deref().unwrap_or(argv[0]);
+        let mut child = match crate::process::spawn(&mut command, app_id) {
+            Ok(child) => child,
+            Err(err) => {
+                self.show_launch_error(launch, err);
+                return;
+            },
+        };
+
+        // Move the application out of our own cgroup.
+        if self.config.launch.systemd_scope {
+            if let Err(err) = dbus::start_app_scope(app_id, child.id()) {
+                error!("Failed to create systemd scope: {err}");
+            }
+        }
+
+        match crate::process::wait_startup(&mut child) {
+            Ok(()) => process::exit(0),
+            Err(err) => self.show_launch_error(launch, err),
+        }
+    }
+
+    /// Show an application launch error to the user.
+    fn show_launch_error(&mut self, launch: &Launch, err: io::Error) {
+        error!("Process launch failed: {err}");
+
+        self.launch_error = Some(format!("Failed to launch {}: {err}", launch.name));
+        self.dirty = true;
+        self.unstall();
+    }
+
+    /// Start the timer for detecting long-press touch sequences.
+    fn start_long_press(&mut self) {
+        let delay = Duration::from_millis(self.config.input.long_press_delay as u64);
+        let timer = self.event_loop.insert_source(Timer::from_duration(delay), |_, _, state| {
+            state.window.long_press();
+            TimeoutAction::Drop
+        });
+        self.long_press_timer =
+            timer.inspect_err(|err| error!("Failed to start long-press timer: {err}")).ok();
+    }
+
+    /// Cancel the active long-press timer.
+    fn cancel_long_press(&mut self) {
+        if let Some(token) = self.long_press_timer.take() {
+            self.event_loop.remove(token);
+        }
+    }
+
+    /// Handle long-press timeout.
+    fn long_press(&mut self) {
+        self.long_press_timer = None;
+
+        let index = match self.touch_state.action {
+            TouchAction::Tap(index) => index,
+            _ => return,
+        };
+
+        // Get the entry's index out of all visible and hidden entries.
+        let entries = self.entries.all().iter().enumerate();
+        let entry = entries.filter(|(_, entry)| entry.visible()).nth(index);
+        let (entry_index, entry) = match entry {
+            Some(entry) if !entry.1.actions.is_empty() => entry,
+            _ => return,
+        };
+
+        self.touch_state.action = TouchAction::LongPress;
+        self.action_menu = Some(ActionMenu { entry: entry_index, len: entry.actions.len() });
+        self.dirty = true;
+        self.unstall();
+    }
+
     /// Get application at the specified location.
     fn entry_at(&self, mut point: Point<f64>) -> Option<usize> {
         point.y -= self.scroll_offset;
@@ -452,6 +860,46 @@ impl Window {
     }
 }
 
+/// Application launch parameters.
+#[derive(Debug)]
+pub struct Launch {
+    /// Command line arguments, used if DBus activation is unavailable.
+    args: Option<Vec<String>>,
+    name: Arc<String>,
+    desktop_id: Option<String>,
+    app_id: Option<String>,
+    /// Application ID used for DBus activation.
+    dbus_app_id: Option<String>,
+    /// Desktop action ID.
+    action: Option<String>,
+    working_directory: Option<PathBuf>,
+    prefers_non_default_gpu: bool,
+    startup_notify: bool,
+    terminal: bool,
+}
+
+impl Launch {
+    fn new(entry: &DesktopEntry, args: Option<&[String]>) -> Self {
+        Self {
+            args: args.map(<[String]>::to_vec),
+            name: entry.name.clone(),
+            desktop_id: entry.desktop_id().map(String::from),
+            app_id: entry.app_id().map(String::from),
+            dbus_app_id: entry.dbus_app_id().map(String::from),
+            working_directory: entry.working_directory.clone(),
+            prefers_non_default_gpu: entry.prefers_non_default_gpu,
+            startup_notify: entry.startup_notify,
+            terminal: entry.terminal,
+            action: None,
+        }
+    }
+
+    /// Get the launched application's ID.
+    pub fn app_id(&self) -> Option<&str> {
+        self.app_id.as_deref()
+    }
+}
+
 /// Skia rendering cache data.
 struct RenderCache {
     font_collection: FontCollection,
@@ -460,8 +908,11 @@ struct RenderCache {
     font_family: String,
     text_paint: Paint,
     png_paint: Paint,
+    colors: Colors,
 
     rendered_entries: HashMap<CacheKey, Image>,
+    /// Quick action icons by their physical size, positioned at the origin.
+    rendered_actions: HashMap<(Icon, u32), Option<RenderEntry>>,
 }
 
 impl RenderCache {
@@ -494,7 +945,9 @@ impl RenderCache {
             text_paint,
             text_style,
             png_paint,
+            colors: config.colors,
             rendered_entries: Default::default(),
+            rendered_actions: Default::default(),
         }
     }
 
@@ -509,7 +962,7 @@ impl RenderCache {
         // Update grid indices for rendering.
         let mut index = 0;
         for entry in desktop_entries.all_mut() {
-            if configuring || !entry.hidden() {
+            if configuring || entry.visible() {
                 entry.grid_index = Some(index);
                 index += 1;
             } else {
@@ -519,6 +972,7 @@ impl RenderCache {
 
         // Prepare Skia image(s) for each entry in parallel.
         let rendered_entries = &self.rendered_entries;
+        let colors = &self.colors;
         let entries = desktop_entries.all().par_iter().filter_map(|entry| {
             let mut origin = grid.origin(entry.grid_index?);
             origin.y += scroll_offset as f32;
@@ -528,7 +982,15 @@ impl RenderCache {
                 return None;
             }
 
-            Self::render_entry(rendered_entries, desktop_entries, grid, configuring, entry, origin)
+            Self::render_entry(
+                rendered_entries,
+                desktop_entries,
+                grid,
+                colors,
+                configuring,
+                entry,
+                origin,
+            )
         });
         entries.collect()
     }
@@ -538,6 +1000,7 @@ impl RenderCache {
         rendered_entries: &HashMap<CacheKey, Image>,
         desktop_entries: &DesktopEntries,
         grid: &Grid,
+        colors: &Colors,
         configuring: bool,
         entry: &DesktopEntry,
         origin: Point<f32>,
@@ -549,9 +1012,11 @@ impl RenderCache {
             Arc::new(String::new())
         };
 
-        // Load image from cache if available.
+        // Find the icon for the next integer scale, to avoid upscaling bitmaps.
         let icon_size = (ICON_SIZE * grid.scale).round() as f32;
-        let icon = desktop_entries.icon(entry, icon_size as u32);
+        let icon = desktop_entries.icon(entry, ICON_SIZE as u32, grid.scale.ceil() as u32);
+
+        // Load image from cache if available.
         let cache_key = CacheKey { icon, name };
         if let Some(cached) = rendered_entries.get(&cache_key) {
             return Some(RenderEntry {
@@ -571,9 +1036,66 @@ impl RenderCache {
         let icon_point = origin + Point::new(icon_padding, icon_padding);
 
         // Draw desktop entry icon.
-        match icon.icon_type() {
-            IconType::Svg => Self::render_svg(origin, name, icon, icon_point, icon_size),
-            IconType::Png => Self::render_png(origin, name, icon, icon_point, icon_size),
+        let mut render_entry = Self::render_icon(icon_point, name, icon, icon_size, colors)?;
+        render_entry.origin = origin;
+        Some(render_entry)
+    }
+
+    /// Render an icon at the specified position.
+    fn render_icon(
+        icon_point: Point<f32>,
+        name: Arc<String>,
+        icon: Icon,
+        icon_size: f32,
+        colors: &Colors,
+    ) -> Option<RenderEntry> {
+        let mut svg_options = SvgOptions::default();
+        if icon.symbolic() {
+            svg_options.style_sheet = Some(symbolic_style_sheet(colors));
+        }
+
+        let (data, icon_type) = icon.load();
+        match icon_type {
+            IconType::Svg => {
+                Self::render_svg(icon_point, name, icon, &data, &svg_options, icon_point, icon_size)
+            },
+            IconType::Svgz => {
+                let data = match decompress_svgz(&data) {
+                    Ok(data) => data,
+                    Err(err) => {
+                        error!("Failed to decompress SVGZ {name}: {err}");
+                        return None;
+                    },
+                };
+                Self::render_svg(icon_point, name, icon, &data, &svg_options, icon_point, icon_size)
+            },
+            IconType::Xpm => {
+                let xpm = match Xpm::decode(&data) {
+                    Some(xpm) => xpm,
+                    None => {
+                        error!("Failed to decode XPM {name}");
+                        return None;
+                    },
+                };
+
+                let size = Size::new(xpm.width as u32, xpm.height as u32);
+                let info = ImageInfo::new(size, ColorType::RGBA8888, AlphaType::Unpremul, None);
+                let data = Data::new_copy(&xpm.pixels);
+                let image = images::raster_from_data(&info, data, xpm.width * 4)?;
+
+                Self::render_image(icon_point, name, icon, image, icon_point, icon_size)
+            },
+            IconType::Raster => {
+                let image = match Image::from_encoded(Data::new_copy(&data)) {
+                    Some(image) => image,
+                    None => {
+                        error!("Failed to render image for {}", name);
+                        return None;
+                    },
+                };
+
+                Self::render_image(icon_point, name, icon, image, icon_point, icon_size)
+            },
         }
     }
 
@@ -582,11 +1104,13 @@ impl RenderCache {
         origin: Point<f32>,
         name: Arc<String>,
         icon: Icon,
+        data: &[u8],
+        options: &SvgOptions,
         icon_point: Point<f32>,
         icon_size: f32,
     ) -> Option<RenderEntry> {
         // Parse SVG data.
-        let svg_tree = match SvgTree::from_data(&icon.load(), &SvgOptions::default()) {
+        let svg_tree = match SvgTree::from_data(data, options) {
             Ok(svg_tree) => svg_tree,
             Err(err) => {
                 error!("Failed to parse SVG {name}: {err}");
@@ -627,24 +1151,16 @@ impl RenderCache {
         })
     }
 
-    /// Render a PNG icon.
-    fn render_png(
+    /// Render a decoded raster icon.
+    fn render_image(
         origin: Point<f32>,
         name: Arc<String>,
         icon: Icon,
+        image: Image,
         mut icon_point: Point<f32>,
         icon_size: f32,
     ) -> Option<RenderEntry> {
-        // Decode PNG image.
-        let image = match Image::from_encoded(Data::new_copy(&icon.load())) {
-            Some(image) => image,
-            None => {
-                error!("Failed to render image for {}", name);
-                return None;
-            },
-        };
-
-        // Ensure PNG aspect ratio is preserved.
+        // Ensure image aspect ratio is preserved.
         let ratio = image.width() as f32 / image.height() as f32;
         let (width, height) = if ratio > 1. {
             icon_point.y += (icon_size - icon_size / ratio) / 2.;
@@ -677,6 +1193,13 @@ impl RenderCache {
     fn update_config(&mut self, scale: f64, config: &Config) -> bool {
         let mut dirty = false;
 
+        // Redraw all entries, since both text and symbolic icons use the colors.
+        if self.colors != config.colors {
+            self.colors = config.colors;
+            self.clear();
+            dirty = true;
+        }
+
         let foreground = config.colors.foreground.as_color4f();
         if self.text_paint.color4f() != foreground {
             self.text_paint.set_color4f(foreground, None);
@@ -702,16 +1225,46 @@ impl RenderCache {
         dirty
     }
 
+    /// Clear all cached textures.
+    fn clear(&mut self) {
+        self.rendered_entries.clear();
+        self.rendered_actions.clear();
+    }
+
     /// Update render scale.
     fn set_scale_factor(&mut self, font_size: f64, scale: f64) {
         // Clear texture cache to redraw icons.
-        self.rendered_entries.clear();
+        self.clear();
 
         self.text_style.set_font_size((font_size * scale) as f32);
         self.paragraph_style.set_text_style(&self.text_style);
     }
 }
 
+/// Get the CSS used to recolor symbolic SVG icons.
+///
+/// Like GTK, this fills all shapes with the foreground color, while using
+/// dedicated colors for the `success`, `warning`, and `error` style classes.
+fn symbolic_style_sheet(colors: &Colors) -> String {
+    let Colors { foreground: fg, success, warning, error, .. } = colors;
+
+    // The `[style]` rules override inline styles, while being more specific than
+    // the class rules, since usvg keeps the least specific `!important` value.
+    format!(
+        "* {{ color: {fg} !important; }}
+        rect, circle, ellipse, path, polygon {{ fill: {fg}; }}
+        rect[style], circle[style], ellipse[style], path[style], polygon[style] {{
+            fill: {fg} !important;
+        }}
+        .fg, .foreground-fill {{ fill: {fg} !important; }}
+        .foreground-stroke {{ stroke: {fg} !important; }}
+        .transparent-fill {{ fill: none !important; }}
+        .success, .success-fill {{ fill: {success} !important; }}
+        .warning, .warning-fill {{ fill: {warning} !important; }}
+        .error, .error-fill {{ fill: {error} !important; }}"
+    )
+}
+
 /// Data necessary to render a desktop entry.
 struct RenderEntry {
     origin: Point<f32>,
@@ -747,9 +1300,54 @@ enum TouchAction {
     #[default]
     None,
     Tap(usize),
+    LongPress,
+    ActionTap(usize),
     Drag,
 }
 
+/// Quick action menu overlay.
+struct ActionMenu {
+    /// Index of the desktop entry in [`DesktopEntries::all`].
+    entry: usize,
+    /// Number of actions in the menu.
+    len: usize,
+}
+
+impl ActionMenu {
+    /// Get the bounds of the entire menu.
+    fn rect(&self, size: Size, scale: f64) -> Rect {
+        let padding = MIN_PADDING * scale;
+        let width = (ACTION_MENU_WIDTH * scale).min(size.width as f64 - 2. * padding);
+        let height = ACTION_HEIGHT * scale * self.len as f64;
+
+        let x = (size.width as f64 - width) / 2.;
+        let y = ((size.height as f64 - height) / 2.).max(padding);
+
+        Rect::from_xywh(x as f32, y as f32, width as f32, height as f32)
+    }
+
+    /// Get the bounds of the action at the specified index.
+    fn action_rect(&self, size: Size, scale: f64, index: usize) -> Rect {
+        let mut rect = self.rect(size, scale);
+        let height = (ACTION_HEIGHT * scale) as f32;
+        rect.top += height * index as f32;
+        rect.bottom = rect.top + height;
+        rect
+    }
+
+    /// Get action index at the specified position.
+    fn index_at(&self, size: Size, scale: f64, point: Point<f64>) -> Option<usize> {
+        let rect = self.rect(size, scale);
+        let (x, y) = (point.x as f32, point.y as f32);
+        if x < rect.left || x >= rect.right || y < rect.top || y >= rect.bottom {
+            return None;
+        }
+
+        let index = ((y - rect.top) / (ACTION_HEIGHT * scale) as f32) as usize;
+        Some(index.min(self.len - 1))
+    }
+}
+
 /// Grid for entry render positioning.
 struct Grid {
     entry_size: Size,
@@ -849,3 +1447,31 @@ impl Grid {
         height as f64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn render_icon_formats() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/icons");
+        let colors = Colors::default();
+
+        for file in ["icon.svg", "icon.svgz", "icon.png", "icon.jpg", "icon.gif", "icon.webp"] {
+            let icon = Icon::from_path(fixtures.join(file));
+            let name = Arc::new(file.to_owned());
+            let entry =
+                RenderCache::render_icon(Point::default(), name, icon, 8., &colors).unwrap();
+            let pixmap = entry.image.peek_pixels().unwrap();
+
+            // Fixtures are 4x2 images with a red left and a blue right half.
+            let y = entry.image_size.height as i32 / 2;
+            let left = pixmap.get_color((1, y));
+            let right = pixmap.get_color((6, y));
+            assert!(left.r() > 240 && left.b() < 16 && left.a() == 255, "{file}: {left:?}");
+            assert!(right.b() > 240 && right.r() < 16 && right.a() == 255, "{file}: {right:?}");
+        }
+    }
+}
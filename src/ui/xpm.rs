@@ -0,0 +1,227 @@
+//! X PixMap image decoding.
+//!
+//! XPM images are still commonly used for legacy application icons in
+//! `/usr/share/pixmaps`, but aren't supported by Skia.
+
+use std::collections::HashMap;
+
+/// Maximum supported image width and height.
+const MAX_SIZE: usize = 1024;
+
+/// Maximum supported characters per pixel, matching libXpm in practice.
+const MAX_CHARS_PER_PIXEL: usize = 4;
+
+/// Decoded XPM image.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Xpm {
+    pub width: usize,
+    pub height: usize,
+    /// Unpremultiplied RGBA pixel data.
+    pub pixels: Vec<u8>,
+}
+
+impl Xpm {
+    /// Decode an XPM3 image.
+    ///
+    /// Returns `None` if the image is malformed or uses unsupported colors.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let data = std::str::from_utf8(data).ok()?;
+        let mut strings = strings(data);
+
+        // Parse `<width> <height> <ncolors> <cpp> [<x_hotspot> <y_hotspot>]` header.
+        let mut values = strings.next()?.split_ascii_whitespace();
+        let mut value = || values.next()?.parse::<usize>().ok();
+        let (width, height, color_count, cpp) = (value()?, value()?, value()?, value()?);
+        if width > MAX_SIZE || height > MAX_SIZE || !(1..=MAX_CHARS_PER_PIXEL).contains(&cpp) {
+            return None;
+        }
+
+        let mut colors = HashMap::new();
+        for _ in 0..color_count {
+            let line = strings.next()?;
+            let key = line.get(..cpp)?;
+            colors.insert(key, parse_color_line(&line[cpp..])?);
+        }
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for _ in 0..height {
+            let line = strings.next()?;
+            if line.len() != width * cpp {
+                return None;
+            }
+
+            for x in 0..width {
+                let key = line.get(x * cpp..(x + 1) * cpp)?;
+                pixels.extend_from_slice(colors.get(key)?);
+            }
+        }
+
+        Some(Self { width, height, pixels })
+    }
+}
+
+/// Iterate over all C string literals, ignoring comments.
+fn strings(data: &str) -> impl Iterator<Item = &str> {
+    let mut rest = data;
+    std::iter::from_fn(move || {
+        loop {
+            let start = rest.find(['"', '/'])?;
+            rest = &rest[start..];
+
+            if let Some(comment) = rest.strip_prefix("/*") {
+                let end = comment.find("*/")?;
+                rest = &comment[end + 2..];
+            } else if let Some(string) = rest.strip_prefix('"') {
+                let end = string.find('"')?;
+                rest = &string[end + 1..];
+                return Some(&string[..end]);
+            } else {
+                rest = &rest[1..];
+            }
+        }
+    })
+}
+
+/// Parse the color of a color table entry, without its pixel characters.
+///
+/// ```text
+/// c #FF0000 m black
+/// ```
+fn parse_color_line(line: &str) -> Option<[u8; 4]> {
+    let mut contexts = Vec::new();
+    let mut tokens = line.split_ascii_whitespace().peekable();
+    while let Some(context) = tokens.next() {
+        // Color names may contain spaces, so consume everything up to the next context.
+        let mut color = tokens.next()?.to_owned();
+        while let Some(token) = tokens.next_if(|token| !is_context(token)) {
+            color.push(' ');
+            color.push_str(token);
+        }
+
+        contexts.push((context, color));
+    }
+
+    // Prefer the color visual, falling back to grayscale and monochrome.
+    let (_, color) = ["c", "g", "g4", "m"]
+        .iter()
+        .find_map(|key| contexts.iter().find(|(context, _)| context == key))?;
+
+    parse_color(color)
+}
+
+/// Check if a token is a color table context key.
+fn is_context(token: &str) -> bool {
+    matches!(token, "c" | "m" | "g" | "g4" | "s")
+}
+
+/// Parse an X11 color.
+fn parse_color(color: &str) -> Option<[u8; 4]> {
+    if color.eq_ignore_ascii_case("none") {
+        return Some([0, 0, 0, 0]);
+    }
+
+    if let Some(hex) = color.strip_prefix('#') {
+        // Colors are specified as `#RGB`, `#RRGGBB` or `#RRRRGGGGBBBB`, with
+        // each channel's most significant digits first.
+        if hex.len() % 3 != 0 || hex.is_empty() || hex.len() > 12 || !hex.is_ascii() {
+            return None;
+        }
+
+        let digits = hex.len() / 3;
+        let channel = |i: usize| {
+            let value = u16::from_str_radix(&hex[i * digits..(i + 1) * digits], 16).ok()?;
+            let max = (1u32 << (digits * 4)) - 1;
+            Some((value as u32 * 255 / max) as u8)
+        };
+
+        return Some([channel(0)?, channel(1)?, channel(2)?, 255]);
+    }
+
+    // Support only the most common named colors.
+    let name = color.to_ascii_lowercase().replace(' ', "");
+    let [r, g, b] = match name.as_str() {
+        "black" => [0, 0, 0],
+        "white" => [255, 255, 255],
+        "red" => [255, 0, 0],
+        "green" => [0, 255, 0],
+        "blue" => [0, 0, 255],
+        "yellow" => [255, 255, 0],
+        "cyan" => [0, 255, 255],
+        "magenta" => [255, 0, 255],
+        "gray" | "grey" => [190, 190, 190],
+        "lightgray" | "lightgrey" => [211, 211, 211],
+        "darkgray" | "darkgrey" => [169, 169, 169],
+        name => {
+            // Handle percentage grays like `gray50`.
+            let percent = name.strip_prefix("gray").or_else(|| name.strip_prefix("grey"))?;
+            let percent = percent.parse::<u32>().ok().filter(|percent| *percent <= 100)?;
+            let value = (percent * 255 + 49) / 100;
+            [value as u8; 3]
+        },
+    };
+
+    Some([r, g, b, 255])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const NONE: [u8; 4] = [0, 0, 0, 0];
+
+    #[test]
+    fn decode_fixture() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/icons/icon.xpm");
+        let xpm = Xpm::decode(&fs::read(path).unwrap()).unwrap();
+
+        assert_eq!(xpm.width, 4);
+        assert_eq!(xpm.height, 2);
+        assert_eq!(xpm.pixels, [RED, RED, BLUE, BLUE, NONE, RED, BLUE, NONE].concat());
+    }
+
+    #[test]
+    fn multi_char_pixels() {
+        let data = br#"/* XPM */
+static char * icon[] = {
+"2 1 2 2 0 0",
+"aa c #f00",
+"ab s background c light grey",
+"abaa"};"#;
+        let xpm = Xpm::decode(data).unwrap();
+
+        assert_eq!(xpm.pixels, [[211, 211, 211, 255], RED].concat());
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("None"), Some(NONE));
+        assert_eq!(parse_color("#00f"), Some(BLUE));
+        assert_eq!(parse_color("#0000FF"), Some(BLUE));
+        assert_eq!(parse_color("#00000000ffff"), Some(BLUE));
+        assert_eq!(parse_color("gray50"), Some([127, 127, 127, 255]));
+        assert_eq!(parse_color("#00"), None);
+        assert_eq!(parse_color("papayawhip"), None);
+
+        assert_eq!(parse_color_line("m white c blue"), Some(BLUE));
+        assert_eq!(parse_color_line("m black"), Some([0, 0, 0, 255]));
+        assert_eq!(parse_color_line("s symbolic"), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(Xpm::decode(b"/* XPM */"), None);
+        assert_eq!(Xpm::decode(br#"/* XPM */ {"1 1 1 1", ". c #f00", "x"}"#), None);
+        assert_eq!(Xpm::decode(br#"/* XPM */ {"2 1 1 1", ". c #f00", "."}"#), None);
+        assert_eq!(Xpm::decode(br#"/* XPM */ {"99999 1 1 1", ". c #f00"}"#), None);
+        assert_eq!(Xpm::decode(br#"/* XPM */ {"1 1 1 0", ". c #f00", "."}"#), None);
+
+        let oversized_cpp = format!(r#"/* XPM */ {{"2 1 1 {}", ". c #f00", ".."}}"#, usize::MAX);
+        assert_eq!(Xpm::decode(oversized_cpp.as_bytes()), None);
+        assert_eq!(Xpm::decode(br#"/* XPM */ {"1 1 1 5", "aaaaa c #f00", "aaaaa"}"#), None);
+    }
+}
@@ -1,5 +1,6 @@
 //! Wayland protocol handling.
 
+use smithay_client_toolkit::activation::{ActivationHandler, ActivationState, RequestDataExt};
 use smithay_client_toolkit::compositor::{CompositorHandler, CompositorState};
 use smithay_client_toolkit::output::{OutputHandler, OutputState};
 use smithay_client_toolkit::reexports::client::globals::GlobalList;
@@ -8,21 +9,22 @@ use smithay_client_toolkit::reexports::client::protocol::wl_pointer::WlPointer;
 use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
 use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
 use smithay_client_toolkit::reexports::client::protocol::wl_touch::WlTouch;
-use smithay_client_toolkit::reexports::client::{Connection, QueueHandle};
+use smithay_client_toolkit::reexports::client::{Connection, Proxy, QueueHandle};
 use smithay_client_toolkit::registry::{ProvidesRegistryState, RegistryState};
 use smithay_client_toolkit::seat::pointer::{
-    BTN_LEFT, PointerEvent, PointerEventKind, PointerHandler,
+    BTN_LEFT, PointerData, PointerEvent, PointerEventKind, PointerHandler,
 };
-use smithay_client_toolkit::seat::touch::TouchHandler;
+use smithay_client_toolkit::seat::touch::{TouchData, TouchHandler};
 use smithay_client_toolkit::seat::{Capability, SeatHandler, SeatState};
 use smithay_client_toolkit::shell::xdg::XdgShell;
 use smithay_client_toolkit::shell::xdg::window::{Window, WindowConfigure, WindowHandler};
 use smithay_client_toolkit::{
-    delegate_compositor, delegate_output, delegate_pointer, delegate_registry, delegate_seat,
-    delegate_touch, delegate_xdg_shell, delegate_xdg_window, registry_handlers,
+    delegate_activation, delegate_compositor, delegate_output, delegate_pointer, delegate_registry,
+    delegate_seat, delegate_touch, delegate_xdg_shell, delegate_xdg_window, registry_handlers,
 };
 
 use crate::geometry::Size;
+use crate::ui::window::Launch;
 use crate::wayland::fractional_scale::{FractionalScaleHandler, FractionalScaleManager};
 use crate::wayland::viewporter::Viewporter;
 use crate::{Error, State};
@@ -34,6 +36,7 @@ pub mod viewporter;
 #[derive(Debug)]
 pub struct ProtocolStates {
     pub fractional_scale: Option<FractionalScaleManager>,
+    pub activation: Option<ActivationState>,
     pub compositor: CompositorState,
     pub registry: RegistryState,
     pub viewporter: Viewporter,
@@ -54,9 +57,19 @@ impl ProtocolStates {
         let viewporter = Viewporter::new(globals, queue)
             .map_err(|err| Error::WaylandProtocol("wp_viewporter", err))?;
         let fractional_scale = FractionalScaleManager::new(globals, queue).ok();
+        let activation = ActivationState::bind(globals, queue).ok();
         let seat = SeatState::new(globals, queue);
 
-        Ok(Self { fractional_scale, compositor, viewporter, xdg_shell, registry, output, seat })
+        Ok(Self {
+            fractional_scale,
+            compositor,
+            activation,
+            viewporter,
+            xdg_shell,
+            registry,
+            output,
+            seat,
+        })
     }
 }
 
@@ -251,12 +264,17 @@ impl TouchHandler for State {
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _touch: &WlTouch,
+        touch: &WlTouch,
         _serial: u32,
         _time: u32,
         _id: i32,
     ) {
-        self.window.touch_up();
+        // Use the serial of the tap's touch down for activation tokens.
+        let data = touch.data::<TouchData>();
+        let seat_serial =
+            data.and_then(|data| Some((data.seat().clone(), data.latest_down_serial()?)));
+
+        self.window.touch_up(self.protocol_states.activation.as_ref(), seat_serial);
     }
 
     fn cancel(&mut self, _connection: &Connection, _queue: &QueueHandle<Self>, _touch: &WlTouch) {}
@@ -289,7 +307,7 @@ impl PointerHandler for State {
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _pointer: &WlPointer,
+        pointer: &WlPointer,
         events: &[PointerEvent],
     ) {
         for event in events {
@@ -298,8 +316,11 @@ impl PointerHandler for State {
                 PointerEventKind::Press { button: BTN_LEFT, .. } => {
                     self.window.touch_down(event.position.into());
                 },
-                PointerEventKind::Release { button: BTN_LEFT, .. } => {
-                    self.window.touch_up();
+                PointerEventKind::Release { button: BTN_LEFT, serial, .. } => {
+                    let seat = pointer.data::<PointerData>().map(|data| data.seat().clone());
+                    let seat_serial = seat.map(|seat| (seat, serial));
+
+                    self.window.touch_up(self.protocol_states.activation.as_ref(), seat_serial);
                 },
                 _ => (),
             }
@@ -308,6 +329,37 @@ impl PointerHandler for State {
 }
 delegate_pointer!(State);
 
+impl ActivationHandler for State {
+    type RequestData = ActivationRequest;
+
+    fn new_token(&mut self, token: String, request: &ActivationRequest) {
+        self.window.launch(&request.launch, Some(&token));
+    }
+}
+delegate_activation!(State, ActivationRequest);
+
+/// Activation token request for launching an application.
+#[derive(Debug)]
+pub struct ActivationRequest {
+    pub launch: Launch,
+    pub seat_serial: Option<(WlSeat, u32)>,
+    pub surface: Option<WlSurface>,
+}
+
+impl RequestDataExt for ActivationRequest {
+    fn app_id(&self) -> Option<&str> {
+        self.launch.app_id()
+    }
+
+    fn seat_and_serial(&self) -> Option<(&WlSeat, u32)> {
+        self.seat_serial.as_ref().map(|(seat, serial)| (seat, *serial))
+    }
+
+    fn surface(&self) -> Option<&WlSurface> {
+        self.surface.as_ref()
+    }
+}
+
 impl ProvidesRegistryState for State {
     registry_handlers![OutputState];
 
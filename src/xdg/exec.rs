@@ -0,0 +1,161 @@
+//! Desktop entry `Exec` key parsing.
+
+use std::path::Path;
+
+/// Values used for expanding `Exec` field codes.
+#[derive(Copy, Clone, Debug)]
+pub struct FieldCodes<'a> {
+    /// Value of the `Icon` key, used for `%i`.
+    pub icon: Option<&'a str>,
+    /// Translated value of the `Name` key, used for `%c`.
+    pub name: &'a str,
+    /// Location of the desktop file, used for `%k`.
+    pub path: &'a Path,
+}
+
+/// Split an `Exec` value into its individual arguments.
+///
+/// The `exec` value must already have its string escape sequences removed.
+///
+/// Since the launcher never passes any files or URLs to the application, all
+/// `%f`/`%F`/`%u`/`%U` field codes are removed.
+///
+/// Returns `None` if the command line is malformed or empty.
+pub fn parse(exec: &str, fields: FieldCodes<'_>) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+
+    // Current argument, `None` if no argument was started yet.
+    let mut arg: Option<String> = None;
+    let mut quoted = false;
+
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // Handle escaped characters inside quoted arguments.
+            '\\' if quoted => match chars.next()? {
+                c @ ('"' | '`' | '$' | '\\') => arg.get_or_insert_default().push(c),
+                // Retain backslashes in front of unreserved characters.
+                c => {
+                    let arg = arg.get_or_insert_default();
+                    arg.push('\\');
+                    arg.push(c);
+                },
+            },
+            '"' if quoted => quoted = false,
+            _ if quoted => arg.get_or_insert_default().push(c),
+            // Start a new quoted section, this can be empty.
+            '"' => {
+                arg.get_or_insert_default();
+                quoted = true;
+            },
+            // Handle reserved characters escaped outside of quotes.
+            '\\' => arg.get_or_insert_default().push(chars.next()?),
+            // Split arguments on unquoted whitespace.
+            ' ' | '\t' | '\n' => args.extend(arg.take()),
+            // Expand field codes.
+            '%' => match chars.next()? {
+                '%' => arg.get_or_insert_default().push('%'),
+                'c' => arg.get_or_insert_default().push_str(fields.name),
+                'k' => arg.get_or_insert_default().push_str(&fields.path.to_string_lossy()),
+                'i' => {
+                    if let Some(icon) = fields.icon.filter(|icon| !icon.is_empty()) {
+                        args.extend(arg.take());
+                        args.push("--icon".into());
+                        args.push(icon.into());
+                    }
+                },
+                // Remove file/URL and deprecated field codes.
+                'f' | 'F' | 'u' | 'U' | 'd' | 'D' | 'n' | 'N' | 'v' | 'm' => (),
+                // Reject unknown field codes.
+                _ => return None,
+            },
+            c => arg.get_or_insert_default().push(c),
+        }
+    }
+
+    // Reject unterminated quotes.
+    if quoted {
+        return None;
+    }
+
+    args.extend(arg);
+
+    if args.is_empty() { None } else { Some(args) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> FieldCodes<'static> {
+        FieldCodes {
+            icon: Some("app-icon"),
+            name: "App Name",
+            path: Path::new("/usr/share/applications/app.desktop"),
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let args = parse("app --flag value", fields()).unwrap();
+        assert_eq!(args, ["app", "--flag", "value"]);
+    }
+
+    #[test]
+    fn file_field_codes() {
+        let args = parse("app %f %F %u %U", fields()).unwrap();
+        assert_eq!(args, ["app"]);
+
+        let args = parse("app --file=%f", fields()).unwrap();
+        assert_eq!(args, ["app", "--file="]);
+    }
+
+    #[test]
+    fn quoted_path() {
+        let args = parse("\"/opt/My App/bin/app\" %U", fields()).unwrap();
+        assert_eq!(args, ["/opt/My App/bin/app"]);
+    }
+
+    #[test]
+    fn quoted_escapes() {
+        let args = parse(r#"sh -c "echo \"\$HOME\" \`id\` \\""#, fields()).unwrap();
+        assert_eq!(args, ["sh", "-c", r#"echo "$HOME" `id` \"#]);
+    }
+
+    #[test]
+    fn empty_quotes() {
+        let args = parse("app \"\" x", fields()).unwrap();
+        assert_eq!(args, ["app", "", "x"]);
+    }
+
+    #[test]
+    fn literal_percent() {
+        let args = parse("printf 100%%", fields()).unwrap();
+        assert_eq!(args, ["printf", "100%"]);
+    }
+
+    #[test]
+    fn icon_field_code() {
+        let args = parse("app %i %u", fields()).unwrap();
+        assert_eq!(args, ["app", "--icon", "app-icon"]);
+
+        let fields = FieldCodes { icon: None, ..fields() };
+        let args = parse("app %i", fields).unwrap();
+        assert_eq!(args, ["app"]);
+    }
+
+    #[test]
+    fn name_and_location_field_codes() {
+        let args = parse("app --class=%c %k", fields()).unwrap();
+        assert_eq!(args, ["app", "--class=App Name", "/usr/share/applications/app.desktop"]);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(parse("", fields()), None);
+        assert_eq!(parse("  %U ", fields()), None);
+        assert_eq!(parse("\"app", fields()), None);
+        assert_eq!(parse("app %", fields()), None);
+        assert_eq!(parse("app %x", fields()), None);
+    }
+}
@@ -0,0 +1,326 @@
+//! GTK icon theme cache parsing.
+//!
+//! The `icon-theme.cache` files created by `gtk-update-icon-cache` list the
+//! theme subdirectories containing each icon, which avoids probing every
+//! subdirectory of a theme for every icon.
+
+use std::fs::{self, File};
+use std::ops::Deref;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::{io, ptr, slice};
+
+use tracing::warn;
+
+/// Supported icon cache major version.
+const MAJOR_VERSION: u16 = 1;
+
+/// Offset indicating the end of a list.
+const END: u32 = u32::MAX;
+
+/// Image flag for icons with an `.xpm` file.
+const HAS_SUFFIX_XPM: u16 = 1;
+/// Image flag for icons with an `.svg` file.
+const HAS_SUFFIX_SVG: u16 = 2;
+/// Image flag for icons with a `.png` file.
+const HAS_SUFFIX_PNG: u16 = 4;
+
+/// Parsed icon theme cache.
+#[derive(Debug)]
+pub struct IconCache {
+    directories: Vec<String>,
+    hash_offset: u32,
+    data: Mmap,
+}
+
+impl IconCache {
+    /// Load the icon cache of a theme directory.
+    ///
+    /// Returns `None` if the cache is missing, invalid, or older than the
+    /// theme directory.
+    pub fn load(theme_dir: &Path) -> Option<Self> {
+        let path = theme_dir.join("icon-theme.cache");
+
+        // Ignore outdated caches, like GTK does.
+        let cache_modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+        let dir_modified = fs::metadata(theme_dir).and_then(|metadata| metadata.modified()).ok()?;
+        if cache_modified < dir_modified {
+            return None;
+        }
+
+        let cache = Self::parse(Mmap::open(&path).ok()?);
+        if cache.is_none() {
+            warn!("Invalid icon cache {path:?}");
+        }
+        cache
+    }
+
+    /// Parse an icon cache's content.
+    fn parse(data: Mmap) -> Option<Self> {
+        if read_u16(&data, 0)? != MAJOR_VERSION {
+            return None;
+        }
+
+        let hash_offset = read_u32(&data, 4)?;
+        let directory_list_offset = read_u32(&data, 8)?;
+
+        // Read the names of all theme subdirectories.
+        let directory_count = read_u32(&data, directory_list_offset)?;
+        let directories = (0..directory_count)
+            .map(|i| {
+                let offset = read_u32(&data, directory_list_offset + 4 + i * 4)?;
+                let name = read_str(&data, offset)?;
+                String::from_utf8(name.into()).ok()
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self { directories, hash_offset, data })
+    }
+
+    /// Check if the cache contains a theme subdirectory.
+    pub fn has_directory(&self, directory: &str) -> bool {
+        self.directories.iter().any(|name| name == directory)
+    }
+
+    /// Check if an icon file exists in a theme subdirectory.
+    pub fn contains(&self, icon_name: &str, directory: &str, extension: &str) -> bool {
+        let flag = match extension {
+            "png" => HAS_SUFFIX_PNG,
+            "svg" => HAS_SUFFIX_SVG,
+            "xpm" => HAS_SUFFIX_XPM,
+            _ => return false,
+        };
+
+        let directory_index = match self.directories.iter().position(|name| name == directory) {
+            Some(directory_index) => directory_index,
+            None => return false,
+        };
+
+        self.contains_internal(icon_name, directory_index, flag).unwrap_or_default()
+    }
+
+    /// Check if an icon file exists in a theme subdirectory.
+    ///
+    /// Returns `None` if the cache is malformed.
+    fn contains_internal(
+        &self,
+        icon_name: &str,
+        directory_index: usize,
+        flag: u16,
+    ) -> Option<bool> {
+        let bucket_count = read_u32(&self.data, self.hash_offset)?;
+        if bucket_count == 0 {
+            return Some(false);
+        }
+
+        // Find the icon's hash chain.
+        let bucket = icon_name_hash(icon_name) % bucket_count;
+        let mut offset = read_u32(&self.data, self.hash_offset + 4 + bucket * 4)?;
+
+        // Limit chain length, to avoid looping forever on cyclic chains.
+        let max_chain_length = self.data.len() / 12;
+        for _ in 0..max_chain_length {
+            if offset == END {
+                return Some(false);
+            }
+
+            let name_offset = read_u32(&self.data, offset + 4)?;
+            if read_str(&self.data, name_offset)? != icon_name.as_bytes() {
+                offset = read_u32(&self.data, offset)?;
+                continue;
+            }
+
+            // Check all images of the icon for the requested directory.
+            let image_list_offset = read_u32(&self.data, offset + 8)?;
+            let image_count = read_u32(&self.data, image_list_offset)?;
+            for i in 0..image_count {
+                let image_offset = image_list_offset + 4 + i * 8;
+                let image_directory = read_u16(&self.data, image_offset)?;
+                let flags = read_u16(&self.data, image_offset + 2)?;
+                if image_directory as usize == directory_index && flags & flag != 0 {
+                    return Some(true);
+                }
+            }
+
+            return Some(false);
+        }
+
+        None
+    }
+}
+
+/// Read-only memory map of a file.
+#[derive(Debug)]
+struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+impl Mmap {
+    /// Map a file into memory.
+    ///
+    /// Like GTK, this relies on `gtk-update-icon-cache` atomically replacing
+    /// the cache instead of modifying it in place.
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len()).map_err(io::Error::other)?;
+
+        // Empty mappings are rejected by the kernel.
+        if len == 0 {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { ptr, len })
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr.cast(), self.len) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr, self.len) };
+    }
+}
+
+// The mapping is read-only and owned exclusively, so it can be shared freely.
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+/// Hash an icon name, matching GTK's hash function.
+fn icon_name_hash(icon_name: &str) -> u32 {
+    // GTK hashes the name as signed characters.
+    icon_name.bytes().fold(0, |hash: u32, byte| {
+        hash.wrapping_shl(5).wrapping_sub(hash).wrapping_add(byte as i8 as u32)
+    })
+}
+
+/// Read a big-endian `u16` at the specified offset.
+fn read_u16(data: &[u8], offset: u32) -> Option<u16> {
+    let offset = offset as usize;
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Read a big-endian `u32` at the specified offset.
+fn read_u32(data: &[u8], offset: u32) -> Option<u32> {
+    let offset = offset as usize;
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Read a NUL-terminated string at the specified offset.
+fn read_str(data: &[u8], offset: u32) -> Option<&[u8]> {
+    let data = data.get(offset as usize..)?;
+    let len = data.iter().position(|byte| *byte == 0)?;
+    Some(&data[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::xdg::tests::TempDir;
+
+    fn fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/icon-theme")
+    }
+
+    fn cache() -> IconCache {
+        IconCache::parse(Mmap::open(&fixture().join("icon-theme.cache")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn directories() {
+        let cache = cache();
+        assert_eq!(cache.directories, ["32x32/apps", "apps/48", "apps/48@2x", "apps/scalable"]);
+        assert!(cache.has_directory("apps/48@2x"));
+        assert!(!cache.has_directory("apps/96"));
+    }
+
+    #[test]
+    fn contains() {
+        let cache = cache();
+
+        assert!(cache.contains("app", "apps/48", "png"));
+        assert!(cache.contains("app", "apps/48@2x", "png"));
+        assert!(cache.contains("app", "32x32/apps", "png"));
+        assert!(cache.contains("app", "apps/scalable", "svg"));
+        assert!(!cache.contains("app", "apps/scalable", "png"));
+
+        assert!(cache.contains("both", "apps/48", "png"));
+        assert!(cache.contains("both", "apps/48", "svg"));
+        assert!(!cache.contains("both", "32x32/apps", "png"));
+
+        assert!(cache.contains("other", "apps/scalable", "svg"));
+        assert!(!cache.contains("other", "apps/48", "png"));
+
+        assert!(!cache.contains("missing", "apps/48", "png"));
+        assert!(!cache.contains("app", "missing", "png"));
+        assert!(!cache.contains("app", "apps/48", "jpg"));
+    }
+
+    #[test]
+    fn hash() {
+        assert_eq!(icon_name_hash(""), 0);
+        assert_eq!(icon_name_hash("a"), 97);
+        assert_eq!(icon_name_hash("ab"), 97 * 31 + 98);
+        assert_eq!(
+            icon_name_hash("\u{e9}"),
+            (0xC3u8 as i8 as u32).wrapping_mul(31).wrapping_add(0xA9u8 as i8 as u32)
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let temp_dir = TempDir::new("invalid-cache");
+        let path = temp_dir.join("icon-theme.cache");
+        let mut data = fs::read(fixture().join("icon-theme.cache")).unwrap();
+
+        fs::write(&path, &data[..10]).unwrap();
+        assert!(IconCache::parse(Mmap::open(&path).unwrap()).is_none());
+
+        data[1] = 2;
+        fs::write(&path, &data).unwrap();
+        assert!(IconCache::parse(Mmap::open(&path).unwrap()).is_none());
+
+        fs::write(&path, "").unwrap();
+        assert!(Mmap::open(&path).is_err());
+    }
+
+    #[test]
+    fn outdated() {
+        let theme_dir = TempDir::new("cache");
+        fs::copy(fixture().join("icon-theme.cache"), theme_dir.join("icon-theme.cache")).unwrap();
+        assert!(IconCache::load(&theme_dir).is_some());
+
+        // Invalidate cache by updating the theme directory.
+        fs::write(theme_dir.join("index.theme"), "").unwrap();
+        let modified = fs::metadata(&*theme_dir).unwrap().modified().unwrap();
+        let cache =
+            fs::File::options().write(true).open(theme_dir.join("icon-theme.cache")).unwrap();
+        cache.set_modified(modified - Duration::from_secs(1)).unwrap();
+        assert!(IconCache::load(&theme_dir).is_none());
+    }
+}
@@ -0,0 +1,533 @@
+//! Icon theme lookup.
+//!
+//! This implements the directory matching of the freedesktop icon theme
+//! specification, using the metadata from each theme's `index.theme`.
+
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+use xdg::BaseDirectories;
+
+use crate::xdg::icon_cache::IconCache;
+use crate::xdg::key_file::{Group, KeyFile};
+
+/// Supported icon file extensions, in order of preference.
+///
+/// GTK's icon cache does not track `.svgz` files, so they are only found in
+/// themes without a cache, matching GTK.
+pub const ICON_EXTENSIONS: [&str; 4] = ["png", "svg", "svgz", "xpm"];
+
+/// Icon theme metadata.
+#[derive(Debug)]
+pub struct IconTheme {
+    /// Names of the themes this theme inherits from.
+    pub inherits: Vec<String>,
+
+    directories: Vec<ThemeDirectory>,
+    roots: Vec<ThemeRoot>,
+}
+
+impl IconTheme {
+    /// Load an icon theme from the icon base directories.
+    ///
+    /// The theme's `index.theme` is read from the first base directory
+    /// containing it, while icons are looked up in all base directories.
+    pub fn load(base_dirs: &[PathBuf], name: &str) -> Option<Self> {
+        let (path, index) = base_dirs.iter().find_map(|base_dir| {
+            let path = base_dir.join(name).join("index.theme");
+            let index = KeyFile::load(&path).ok()?;
+            Some((path, index))
+        })?;
+
+        let mut theme = Self::from_index(&index, &path)?;
+
+        // Find the theme in all base directories.
+        theme.roots = base_dirs
+            .iter()
+            .map(|base_dir| base_dir.join(name))
+            .filter(|path| path.is_dir())
+            .map(|path| ThemeRoot { cache: IconCache::load(&path), path })
+            .collect();
+
+        // Find all existing instances of every theme directory.
+        for directory in &mut theme.directories {
+            let roots = theme.roots.iter().enumerate();
+            let roots = roots.filter(|(_, root)| root.has_directory(&directory.name));
+            directory.roots = roots.map(|(i, _)| i).collect();
+        }
+        theme.directories.retain(|directory| !directory.roots.is_empty());
+
+        Some(theme)
+    }
+
+    /// Parse the theme metadata from its `index.theme`.
+    fn from_index(index: &KeyFile, path: &Path) -> Option<Self> {
+        let group = match index.group("Icon Theme") {
+            Some(group) => group,
+            None => {
+                warn!("Missing [Icon Theme] group in {path:?}");
+                return None;
+            },
+        };
+
+        let mut directory_names = group.list("Directories", ',');
+        for directory in group.list("ScaledDirectories", ',') {
+            if !directory_names.contains(&directory) {
+                directory_names.push(directory);
+            }
+        }
+
+        let directories = directory_names
+            .into_iter()
+            .filter_map(|name| {
+                let directory = ThemeDirectory::new(&name, index.group(&name)?);
+                if directory.is_none() {
+                    warn!("Missing size for icon theme directory {name:?} in {path:?}");
+                }
+                directory
+            })
+            .collect();
+
+        Some(Self { directories, inherits: group.list("Inherits", ','), roots: Vec::new() })
+    }
+
+    /// Find the best icon file for the requested size.
+    ///
+    /// Icons from a directory matching the size are always preferred, falling
+    /// back to the icon with the closest size otherwise.
+    pub fn lookup(&self, icon_name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        // Find the first icon in a directory matching the requested size.
+        let mut matching = self.directories.iter().filter(|dir| dir.matches_size(size, scale));
+        if let Some(path) = matching.find_map(|directory| self.find_icon(directory, icon_name)) {
+            return Some(path);
+        }
+
+        // Fall back to the icon with the closest size.
+        let mut closest = None;
+        let mut min_distance = u32::MAX;
+        for directory in &self.directories {
+            // Skip directories which cannot improve on the current match, or were checked
+            // already.
+            let distance = directory.size_distance(size, scale);
+            if distance >= min_distance || directory.matches_size(size, scale) {
+                continue;
+            }
+
+            if let Some(path) = self.find_icon(directory, icon_name) {
+                closest = Some(path);
+                min_distance = distance;
+            }
+        }
+        closest
+    }
+
+    /// Find an icon file in a theme directory.
+    fn find_icon(&self, directory: &ThemeDirectory, icon_name: &str) -> Option<PathBuf> {
+        let mut roots = directory.roots.iter().map(|i| &self.roots[*i]);
+        roots.find_map(|root| root.find_icon(&directory.name, icon_name))
+    }
+}
+
+/// Get the icon theme configured for GTK or KDE.
+pub fn system_theme() -> Option<String> {
+    let config_home = BaseDirectories::new().get_config_home()?;
+
+    let gtk_theme = || {
+        let settings = KeyFile::load(&config_home.join("gtk-3.0/settings.ini")).ok()?;
+        settings.group("Settings")?.string("gtk-icon-theme-name")
+    };
+    let kde_theme = || {
+        let settings = KeyFile::load(&config_home.join("kdeglobals")).ok()?;
+        settings.group("Icons")?.string("Theme")
+    };
+
+    gtk_theme().or_else(kde_theme).filter(|theme| !theme.is_empty())
+}
+
+/// Theme directory inside an icon base directory.
+#[derive(Debug)]
+struct ThemeRoot {
+    path: PathBuf,
+    /// GTK icon cache, if it is up to date.
+    cache: Option<IconCache>,
+}
+
+impl ThemeRoot {
+    /// Check if a theme subdirectory exists.
+    fn has_directory(&self, directory: &str) -> bool {
+        match &self.cache {
+            Some(cache) => cache.has_directory(directory),
+            None => self.path.join(directory).is_dir(),
+        }
+    }
+
+    /// Find an icon file in a theme subdirectory.
+    fn find_icon(&self, directory: &str, icon_name: &str) -> Option<PathBuf> {
+        let path = |ext| self.path.join(directory).join(format!("{icon_name}.{ext}"));
+
+        // Avoid filesystem access if the icon cache is available.
+        match &self.cache {
+            Some(cache) => {
+                let mut extensions = ICON_EXTENSIONS.iter();
+                extensions.find(|ext| cache.contains(icon_name, directory, ext)).map(path)
+            },
+            None => ICON_EXTENSIONS.iter().map(path).find(|file| file.is_file()),
+        }
+    }
+}
+
+/// Icon theme subdirectory metadata.
+#[derive(PartialEq, Eq, Debug)]
+struct ThemeDirectory {
+    /// Path relative to the theme's directory.
+    name: String,
+    /// Indices of the theme roots containing this directory.
+    roots: Vec<usize>,
+    directory_type: DirectoryType,
+    size: u32,
+    scale: u32,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+impl ThemeDirectory {
+    /// Parse a theme directory from its `index.theme` group.
+    ///
+    /// Returns `None` if the required `Size` key is missing.
+    fn new(name: &str, group: &Group) -> Option<Self> {
+        let size = group.integer("Size")?;
+
+        let directory_type = match group.string("Type").as_deref() {
+            Some("Fixed") => DirectoryType::Fixed,
+            Some("Scalable") => DirectoryType::Scalable,
+            Some("Threshold") | None => DirectoryType::Threshold,
+            Some(directory_type) => {
+                warn!("Invalid icon directory type {directory_type:?}");
+                DirectoryType::Threshold
+            },
+        };
+
+        Some(Self {
+            directory_type,
+            size,
+            name: name.into(),
+            roots: Vec::new(),
+            scale: group.integer("Scale").unwrap_or(1),
+            min_size: group.integer("MinSize").unwrap_or(size),
+            max_size: group.integer("MaxSize").unwrap_or(size),
+            threshold: group.integer("Threshold").unwrap_or(2),
+        })
+    }
+
+    /// Check if icons in this directory can be used for a size without
+    /// scaling.
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+
+        match self.directory_type {
+            DirectoryType::Fixed => self.size == size,
+            DirectoryType::Scalable => (self.min_size..=self.max_size).contains(&size),
+            DirectoryType::Threshold => {
+                let min_size = self.size.saturating_sub(self.threshold);
+                (min_size..=self.size + self.threshold).contains(&size)
+            },
+        }
+    }
+
+    /// Get the distance between this directory's icon size and the requested
+    /// size in physical pixels.
+    fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let size = size * scale;
+
+        let (min_size, max_size) = match self.directory_type {
+            DirectoryType::Fixed => (self.size, self.size),
+            DirectoryType::Scalable => (self.min_size, self.max_size),
+            DirectoryType::Threshold => {
+                (self.size.saturating_sub(self.threshold), self.size + self.threshold)
+            },
+        };
+
+        if size < min_size * self.scale {
+            min_size * self.scale - size
+        } else {
+            size.saturating_sub(max_size * self.scale)
+        }
+    }
+}
+
+/// Icon theme directory size matching type.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum DirectoryType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::time::Instant;
+
+    use super::*;
+    use crate::xdg::tests::TempDir;
+
+    fn theme(index: &str) -> IconTheme {
+        let path = Path::new("index.theme");
+        IconTheme::from_index(&KeyFile::parse(index, path), path).unwrap()
+    }
+
+    fn directory(theme: &IconTheme, name: &str) -> usize {
+        theme.directories.iter().position(|directory| directory.name == name).unwrap()
+    }
+
+    const INDEX: &str = "\
+[Icon Theme]
+Name=Test
+Inherits=Parent,hicolor
+Directories=apps/48,apps/scalable,32x32/apps,missing
+ScaledDirectories=apps/48@2x
+
+[apps/48]
+Size=48
+Type=Fixed
+
+[apps/48@2x]
+Size=48
+Scale=2
+Type=Fixed
+
+[apps/scalable]
+Size=64
+MinSize=16
+MaxSize=256
+Type=Scalable
+
+[32x32/apps]
+Size=32
+";
+
+    #[test]
+    fn parse_index() {
+        let theme = theme(INDEX);
+
+        assert_eq!(theme.inherits, ["Parent", "hicolor"]);
+        assert_eq!(theme.directories, [
+            ThemeDirectory {
+                roots: Vec::new(),
+                name: "apps/48".into(),
+                directory_type: DirectoryType::Fixed,
+                size: 48,
+                scale: 1,
+                min_size: 48,
+                max_size: 48,
+                threshold: 2,
+            },
+            ThemeDirectory {
+                roots: Vec::new(),
+                name: "apps/scalable".into(),
+                directory_type: DirectoryType::Scalable,
+                size: 64,
+                scale: 1,
+                min_size: 16,
+                max_size: 256,
+                threshold: 2,
+            },
+            ThemeDirectory {
+                roots: Vec::new(),
+                name: "32x32/apps".into(),
+                directory_type: DirectoryType::Threshold,
+                size: 32,
+                scale: 1,
+                min_size: 32,
+                max_size: 32,
+                threshold: 2,
+            },
+            ThemeDirectory {
+                roots: Vec::new(),
+                name: "apps/48@2x".into(),
+                directory_type: DirectoryType::Fixed,
+                size: 48,
+                scale: 2,
+                min_size: 48,
+                max_size: 48,
+                threshold: 2,
+            },
+        ]);
+    }
+
+    #[test]
+    fn matches_size() {
+        let theme = theme(INDEX);
+        let fixed = &theme.directories[directory(&theme, "apps/48")];
+        let scalable = &theme.directories[directory(&theme, "apps/scalable")];
+        let threshold = &theme.directories[directory(&theme, "32x32/apps")];
+        let scaled = &theme.directories[directory(&theme, "apps/48@2x")];
+
+        assert!(fixed.matches_size(48, 1));
+        assert!(!fixed.matches_size(47, 1));
+        assert!(!fixed.matches_size(48, 2));
+
+        assert!(scalable.matches_size(16, 1));
+        assert!(scalable.matches_size(256, 1));
+        assert!(!scalable.matches_size(512, 1));
+
+        assert!(threshold.matches_size(30, 1));
+        assert!(threshold.matches_size(34, 1));
+        assert!(!threshold.matches_size(35, 1));
+
+        assert!(scaled.matches_size(48, 2));
+        assert!(!scaled.matches_size(96, 1));
+    }
+
+    #[test]
+    fn size_distance() {
+        let theme = theme(INDEX);
+        let fixed = &theme.directories[directory(&theme, "apps/48")];
+        let scalable = &theme.directories[directory(&theme, "apps/scalable")];
+        let threshold = &theme.directories[directory(&theme, "32x32/apps")];
+        let scaled = &theme.directories[directory(&theme, "apps/48@2x")];
+
+        assert_eq!(fixed.size_distance(48, 1), 0);
+        assert_eq!(fixed.size_distance(64, 1), 16);
+        assert_eq!(fixed.size_distance(32, 1), 16);
+
+        assert_eq!(scalable.size_distance(128, 1), 0);
+        assert_eq!(scalable.size_distance(8, 1), 8);
+        assert_eq!(scalable.size_distance(300, 1), 44);
+
+        assert_eq!(threshold.size_distance(33, 1), 0);
+        assert_eq!(threshold.size_distance(40, 1), 6);
+
+        assert_eq!(scaled.size_distance(48, 2), 0);
+        assert_eq!(scaled.size_distance(100, 1), 4);
+    }
+
+    #[test]
+    fn lookup() {
+        let base_dir = fixture("lookup", INDEX, &[
+            "apps/48/app.png",
+            "apps/scalable/app.svg",
+            "apps/scalable/compressed.svgz",
+            "32x32/apps/app.png",
+            "32x32/apps/other.png",
+        ]);
+        let base_dirs = [base_dir.to_path_buf()];
+        let icon_dir = base_dirs[0].join("test");
+        let theme = IconTheme::load(&base_dirs, "test").unwrap();
+
+        assert_eq!(theme.lookup("app", 48, 1), Some(icon_dir.join("apps/48/app.png")));
+        assert_eq!(theme.lookup("app", 32, 1), Some(icon_dir.join("apps/scalable/app.svg")));
+        assert_eq!(theme.lookup("app", 512, 1), Some(icon_dir.join("apps/scalable/app.svg")));
+        assert_eq!(theme.lookup("app", 8, 1), Some(icon_dir.join("apps/scalable/app.svg")));
+        assert_eq!(theme.lookup("other", 48, 1), Some(icon_dir.join("32x32/apps/other.png")));
+        assert_eq!(
+            theme.lookup("compressed", 48, 1),
+            Some(icon_dir.join("apps/scalable/compressed.svgz"))
+        );
+        assert_eq!(theme.lookup("missing", 48, 1), None);
+
+        fs::remove_file(icon_dir.join("apps/scalable/app.svg")).unwrap();
+        assert_eq!(theme.lookup("app", 16, 1), Some(icon_dir.join("32x32/apps/app.png")));
+        assert_eq!(theme.lookup("app", 44, 1), Some(icon_dir.join("apps/48/app.png")));
+    }
+
+    #[test]
+    fn lookup_cached() {
+        // Copy cache without any icons, to ensure no files are accessed.
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/icon-theme");
+        let index = fs::read_to_string(fixture.join("index.theme")).unwrap();
+        let base_dir = self::fixture("lookup-cached", &index, &[]);
+        let base_dirs = [base_dir.to_path_buf()];
+        let icon_dir = base_dirs[0].join("test");
+        fs::copy(fixture.join("icon-theme.cache"), icon_dir.join("icon-theme.cache")).unwrap();
+
+        let theme = IconTheme::load(&base_dirs, "test").unwrap();
+
+        assert_eq!(theme.lookup("app", 48, 1), Some(icon_dir.join("apps/48/app.png")));
+        assert_eq!(theme.lookup("app", 48, 2), Some(icon_dir.join("apps/48@2x/app.png")));
+        assert_eq!(theme.lookup("app", 48, 3), Some(icon_dir.join("apps/scalable/app.svg")));
+        assert_eq!(theme.lookup("both", 48, 1), Some(icon_dir.join("apps/48/both.png")));
+        assert_eq!(theme.lookup("both", 48, 2), Some(icon_dir.join("apps/48/both.png")));
+        assert_eq!(theme.lookup("other", 32, 1), Some(icon_dir.join("apps/scalable/other.svg")));
+        assert_eq!(theme.lookup("missing", 48, 1), None);
+    }
+
+    /// Compare lazy icon lookups to indexing all icons of a theme upfront.
+    ///
+    /// Run with `cargo test --release -- --ignored startup_benchmark`.
+    #[test]
+    #[ignore]
+    fn startup_benchmark() {
+        const CONTEXTS: [&str; 5] = ["actions", "apps", "devices", "mimetypes", "places"];
+        const SIZES: [u32; 8] = [16, 22, 24, 32, 48, 64, 96, 128];
+        const ICONS: usize = 1000;
+        const LOOKUPS: usize = 80;
+
+        // Generate a theme with a size similar to common icon themes.
+        let mut directories = Vec::new();
+        let mut index = String::new();
+        for size in SIZES {
+            for context in CONTEXTS {
+                let directory = format!("{size}x{size}/{context}");
+                index.push_str(&format!("[{directory}]\nSize={size}\nType=Fixed\n"));
+                directories.push(directory);
+            }
+        }
+        let index = format!("[Icon Theme]\nDirectories={}\n{index}", directories.join(","));
+        let icons: Vec<_> = directories
+            .iter()
+            .flat_map(|directory| (0..ICONS).map(move |i| format!("{directory}/icon-{i}.png")))
+            .collect();
+        let icons: Vec<_> = icons.iter().map(String::as_str).collect();
+        let base_dir = fixture("benchmark", &index, &icons);
+        let base_dirs = [base_dir.to_path_buf()];
+
+        // Index all icons, like the previous eager icon loader.
+        let start = Instant::now();
+        let mut indexed: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let theme = IconTheme::load(&base_dirs, "test").unwrap();
+        let directories = theme.directories.iter().flat_map(|directory| {
+            directory.roots.iter().map(|i| theme.roots[*i].path.join(&directory.name))
+        });
+        for directory in directories {
+            for file in fs::read_dir(directory).unwrap().flatten() {
+                let file_name = file.file_name().into_string().unwrap();
+                let (icon_name, _) = file_name.rsplit_once('.').unwrap();
+                indexed.entry(icon_name.into()).or_default().push(file.path());
+            }
+        }
+        let eager = start.elapsed();
+        assert_eq!(indexed.values().map(Vec::len).sum::<usize>(), icons.len());
+
+        // Resolve enough icons for a full drawer on demand.
+        let start = Instant::now();
+        let theme = IconTheme::load(&base_dirs, "test").unwrap();
+        for i in 0..LOOKUPS {
+            let icon_name = format!("icon-{}", i * ICONS / LOOKUPS);
+            assert!(theme.lookup(&icon_name, 64, 1).is_some());
+        }
+        let lazy = start.elapsed();
+
+        assert!(lazy < eager, "lazy lookup ({lazy:?}) slower than eager indexing ({eager:?})");
+    }
+
+    /// Create a temporary icon base directory containing the theme `test`.
+    fn fixture(name: &str, index: &str, icons: &[&str]) -> TempDir {
+        let base_dir = TempDir::new(name);
+        let theme_dir = base_dir.join("test");
+        fs::create_dir_all(&theme_dir).unwrap();
+        fs::write(theme_dir.join("index.theme"), index).unwrap();
+
+        for icon in icons {
+            let path = theme_dir.join(icon);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        base_dir
+    }
+}
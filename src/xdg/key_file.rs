@@ -0,0 +1,503 @@
+//! Desktop entry key file parsing.
+//!
+//! This implements the group and key/value grammar shared by desktop entries
+//! and icon theme `index.theme` files.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::{Path, PathBuf};
+use std::{fs, io, mem};
+
+use tracing::warn;
+
+use crate::xdg::locale::{self, Locale, LocaleString};
+
+/// Parsed key file.
+#[derive(Default, Debug)]
+pub struct KeyFile {
+    groups: HashMap<String, Group>,
+}
+
+impl KeyFile {
+    /// Read and parse a key file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(Self::parse(&content, path))
+    }
+
+    /// Parse a key file's content.
+    ///
+    /// Invalid lines are logged and ignored, `path` is only used for these
+    /// error messages.
+    ///
+    /// Keys before the first group header are stored in a group with an empty
+    /// name.
+    pub fn parse(content: &str, path: &Path) -> Self {
+        let mut key_file = Self::default();
+
+        let new_group = || Group { path: path.into(), values: HashMap::new() };
+
+        // Current group, `None` if the last group header was invalid.
+        let mut group = Some(key_file.groups.entry(String::new()).or_insert_with(new_group));
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim_start();
+
+            // Ignore comments and blank lines.
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // Start a new group on group headers.
+            if let Some(header) = line.strip_prefix('[') {
+                let name = match header.trim_end().strip_suffix(']') {
+                    Some(name) if is_valid_group(name) => name,
+                    _ => {
+                        warn!("{path:?}:{}: Invalid group header {line:?}", i + 1);
+                        group = None;
+                        continue;
+                    },
+                };
+
+                group = match key_file.groups.entry(name.into()) {
+                    Entry::Occupied(entry) => {
+                        warn!("{path:?}:{}: Duplicate group {name:?}", i + 1);
+                        Some(entry.into_mut())
+                    },
+                    Entry::Vacant(entry) => Some(entry.insert(new_group())),
+                };
+
+                continue;
+            }
+
+            // Get K/V pairs, allowing for whitespace around the assignment operator.
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) if is_valid_key(key.trim_end()) => {
+                    (key.trim_end(), value.trim_start())
+                },
+                _ => {
+                    warn!("{path:?}:{}: Invalid line {line:?}", i + 1);
+                    continue;
+                },
+            };
+
+            // Ignore keys in invalid groups.
+            let group = match &mut group {
+                Some(group) => group,
+                None => continue,
+            };
+
+            // Later values overwrite existing ones.
+            let value = Value { raw: value.into(), line: i + 1 };
+            if group.values.insert(key.into(), value).is_some() {
+                warn!("{path:?}:{}: Duplicate key {key:?}", i + 1);
+            }
+        }
+
+        // Remove empty placeholder group for keys without a group.
+        if key_file.groups.get("").is_some_and(|group| group.values.is_empty()) {
+            key_file.groups.remove("");
+        }
+
+        key_file
+    }
+
+    /// Get a group by its name.
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.get(name)
+    }
+}
+
+/// Key file group.
+#[derive(Debug)]
+pub struct Group {
+    /// Raw values, including escape sequences.
+    values: HashMap<String, Value>,
+    /// Path of the key file, used for error messages.
+    path: PathBuf,
+}
+
+impl Group {
+    /// Get a string value.
+    pub fn string(&self, key: &str) -> Option<String> {
+        self.values.get(key).map(|value| unescape(&value.raw))
+    }
+
+    /// Get the value of a localized key best matching the locale.
+    pub fn locale_string(&self, key: &str, locale: Option<&Locale>) -> Option<String> {
+        self.raw_locale_string(key, locale).map(|value| unescape(&value))
+    }
+
+    /// Get a boolean value.
+    ///
+    /// Returns `None` if the key is missing or not a valid boolean.
+    pub fn boolean(&self, key: &str) -> Option<bool> {
+        let value = self.values.get(key)?;
+        match value.raw.trim_end() {
+            "true" => Some(true),
+            "false" => Some(false),
+            raw => {
+                warn!("{:?}:{}: Invalid boolean for {key:?}: {raw:?}", self.path, value.line);
+                None
+            },
+        }
+    }
+
+    /// Get an unsigned integer value.
+    ///
+    /// Returns `None` if the key is missing or not a valid integer.
+    pub fn integer(&self, key: &str) -> Option<u32> {
+        let value = self.values.get(key)?;
+        let raw = value.raw.trim_end();
+        match raw.parse() {
+            Ok(integer) => Some(integer),
+            Err(_) => {
+                warn!("{:?}:{}: Invalid integer for {key:?}: {raw:?}", self.path, value.line);
+                None
+            },
+        }
+    }
+
+    /// Get a list of strings.
+    ///
+    /// Returns an empty list if the key is missing.
+    pub fn list(&self, key: &str, separator: char) -> Vec<String> {
+        self.values.get(key).map(|value| split_list(&value.raw, separator)).unwrap_or_default()
+    }
+
+    /// Get the raw value of a localized key best matching the locale.
+    fn raw_locale_string(&self, key: &str, locale: Option<&Locale>) -> Option<String> {
+        let mut best_match = LocaleString::default();
+        for (full_key, value) in &self.values {
+            match locale::split_key(full_key) {
+                (name, key_locale) if name == key => {
+                    best_match.offer(locale, key_locale, &value.raw)
+                },
+                _ => (),
+            }
+        }
+        best_match.into_value()
+    }
+}
+
+/// Raw key file value.
+#[derive(Debug)]
+struct Value {
+    raw: String,
+    /// Line number, used for error messages.
+    line: usize,
+}
+
+/// Set a key's value in a key file's content.
+///
+/// All other lines are preserved verbatim, including comments and the order of
+/// keys and groups. Missing keys are added to the end of their group, and the
+/// group is created if it does not exist yet.
+///
+/// New lines use the line ending of the existing content.
+pub fn set_value(content: &str, group: &str, key: &str, value: &str) -> String {
+    let mut output = String::with_capacity(content.len() + key.len() + value.len() + 3);
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+
+    // Position after the last key or header of the target group.
+    let mut insert_position = None;
+    let mut current_group = "";
+    let mut found = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|name| name.strip_suffix(']')) {
+            current_group = name;
+        } else if current_group != group || trimmed.is_empty() || trimmed.starts_with('#') {
+            output.push_str(line);
+            continue;
+        } else if trimmed.split_once('=').is_some_and(|(k, _)| k.trim_end() == key) {
+            // Replace value, retaining the original line ending.
+            let line_ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+            output.push_str(&format!("{key}={value}{line_ending}"));
+            found = true;
+            continue;
+        }
+
+        output.push_str(line);
+
+        if current_group == group {
+            insert_position = Some(output.len());
+        }
+    }
+
+    if found {
+        return output;
+    }
+
+    match insert_position {
+        Some(position) if output[..position].ends_with('\n') => {
+            output.insert_str(position, &format!("{key}={value}{newline}"));
+        },
+        Some(position) => output.insert_str(position, &format!("{newline}{key}={value}")),
+        None => {
+            if !output.is_empty() && !output.ends_with('\n') {
+                output.push_str(newline);
+            }
+            output.push_str(&format!("[{group}]{newline}{key}={value}{newline}"));
+        },
+    }
+
+    output
+}
+
+/// Check if a group name is valid.
+fn is_valid_group(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['[', ']']) && !name.chars().any(char::is_control)
+}
+
+/// Check if a key, including its optional locale, is valid.
+fn is_valid_key(key: &str) -> bool {
+    let (name, locale) = locale::split_key(key);
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && locale.is_none_or(|locale| !locale.is_empty() && !locale.contains(['[', ']']))
+}
+
+/// Remove escape sequences from a string value.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            // Retain unknown escape sequences verbatim.
+            Some(c) => {
+                unescaped.push('\\');
+                unescaped.push(c);
+            },
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// Split a list value into its unescaped elements.
+fn split_list(value: &str, separator: char) -> Vec<String> {
+    let mut elements = Vec::new();
+    let mut element = String::new();
+
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // Retain escape sequences, to unescape them after splitting.
+            '\\' => match chars.next() {
+                Some(c) if c == separator => element.push(c),
+                Some(c) => {
+                    element.push('\\');
+                    element.push(c);
+                },
+                None => element.push('\\'),
+            },
+            c if c == separator => elements.push(unescape(&mem::take(&mut element))),
+            c => element.push(c),
+        }
+    }
+
+    // Add last element, if the list has no trailing separator.
+    if !element.is_empty() {
+        elements.push(unescape(&element));
+    }
+
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> KeyFile {
+        KeyFile::parse(content, Path::new("test.desktop"))
+    }
+
+    #[test]
+    fn groups() {
+        let key_file = parse(
+            "[Desktop Entry]\nName=App\n\n[Desktop Action new]\nName=New\n[Other Group]\nX-Key=1",
+        );
+
+        let entry = key_file.group("Desktop Entry").unwrap();
+        assert_eq!(entry.string("Name").as_deref(), Some("App"));
+        let action = key_file.group("Desktop Action new").unwrap();
+        assert_eq!(action.string("Name").as_deref(), Some("New"));
+        let other = key_file.group("Other Group").unwrap();
+        assert_eq!(other.string("X-Key").as_deref(), Some("1"));
+
+        assert!(key_file.group("").is_none());
+    }
+
+    #[test]
+    fn comments() {
+        let key_file =
+            parse("# Name=Comment\n[Desktop Entry]\n  # Exec=comment\nName=App # not a comment");
+
+        let entry = key_file.group("Desktop Entry").unwrap();
+        assert_eq!(entry.string("Name").as_deref(), Some("App # not a comment"));
+        assert_eq!(entry.string("Exec"), None);
+        assert!(key_file.group("").is_none());
+    }
+
+    #[test]
+    fn whitespace_around_assignment() {
+        let key_file = parse("[Desktop Entry]\nName = App Name \n");
+        let entry = key_file.group("Desktop Entry").unwrap();
+        assert_eq!(entry.string("Name").as_deref(), Some("App Name "));
+    }
+
+    #[test]
+    fn keys_without_group() {
+        let key_file = parse("NoDisplay=true\n");
+        let group = key_file.group("").unwrap();
+        assert_eq!(group.boolean("NoDisplay"), Some(true));
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let key_file = parse("[Desktop Entry]\nName=App\ngarbage\nN@me=x\n[Broken\nExec=x\n");
+
+        let entry = key_file.group("Desktop Entry").unwrap();
+        assert_eq!(entry.string("Name").as_deref(), Some("App"));
+        assert_eq!(entry.string("N@me"), None);
+        assert_eq!(entry.string("Exec"), None);
+    }
+
+    #[test]
+    fn duplicates() {
+        let key_file = parse("[A]\nKey=1\nKey=2\n[B]\nKey=3\n[A]\nOther=4");
+
+        let a = key_file.group("A").unwrap();
+        assert_eq!(a.string("Key").as_deref(), Some("2"));
+        assert_eq!(a.string("Other").as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn escapes() {
+        let key_file = parse(
+            r"[A]
+Key=a\sb\nc\td\re\\f\;g\x",
+        );
+        let group = key_file.group("A").unwrap();
+        assert_eq!(group.string("Key").as_deref(), Some("a b\nc\td\re\\f\\;g\\x"));
+    }
+
+    #[test]
+    fn booleans() {
+        let key_file = parse("[A]\nYes=true\nNo=false\nInvalid=yes");
+        let group = key_file.group("A").unwrap();
+        assert_eq!(group.boolean("Yes"), Some(true));
+        assert_eq!(group.boolean("No"), Some(false));
+        assert_eq!(group.boolean("Invalid"), None);
+        assert_eq!(group.boolean("Missing"), None);
+    }
+
+    #[test]
+    fn value_lines() {
+        let key_file = parse("[A]\nSize=48\n\n# Comment\nInvalid=big");
+        let group = key_file.group("A").unwrap();
+        assert_eq!(group.path, Path::new("test.desktop"));
+        assert_eq!(group.values["Size"].line, 2);
+        assert_eq!(group.values["Invalid"].line, 5);
+    }
+
+    #[test]
+    fn integers() {
+        let key_file = parse("[A]\nSize=48\nNegative=-1\nInvalid=big");
+        let group = key_file.group("A").unwrap();
+        assert_eq!(group.integer("Size"), Some(48));
+        assert_eq!(group.integer("Negative"), None);
+        assert_eq!(group.integer("Invalid"), None);
+        assert_eq!(group.integer("Missing"), None);
+    }
+
+    #[test]
+    fn lists() {
+        let key_file = parse(
+            r"[A]
+Trailing=a;b;c;
+NoTrailing=a;b
+Escaped=a\;b;c\sd;
+Comma=a,b\,c",
+        );
+        let group = key_file.group("A").unwrap();
+        assert_eq!(group.list("Trailing", ';'), ["a", "b", "c"]);
+        assert_eq!(group.list("NoTrailing", ';'), ["a", "b"]);
+        assert_eq!(group.list("Escaped", ';'), ["a;b", "c d"]);
+        assert_eq!(group.list("Comma", ','), ["a", "b,c"]);
+        assert!(group.list("Missing", ';').is_empty());
+    }
+
+    #[test]
+    fn localized() {
+        let key_file = parse("[A]\nName[de]=Fuh\nName=Foo");
+        let group = key_file.group("A").unwrap();
+
+        let de = Locale::parse("de_DE.UTF-8");
+        assert_eq!(group.locale_string("Name", de.as_ref()).as_deref(), Some("Fuh"));
+
+        let fr = Locale::parse("fr_FR");
+        assert_eq!(group.locale_string("Name", fr.as_ref()).as_deref(), Some("Foo"));
+        assert_eq!(group.locale_string("Name", None).as_deref(), Some("Foo"));
+    }
+
+    #[test]
+    fn set_existing_value() {
+        let content = "# Comment\n[Desktop \
+                       Entry]\nName=App\nNoDisplay=false\nExec=app\n\n[Desktop Action \
+                       new]\nNoDisplay=false\n";
+        let content = set_value(content, "Desktop Entry", "NoDisplay", "true");
+        assert_eq!(
+            content,
+            "# Comment\n[Desktop Entry]\nName=App\nNoDisplay=true\nExec=app\n\n[Desktop Action \
+             new]\nNoDisplay=false\n"
+        );
+    }
+
+    #[test]
+    fn set_missing_value() {
+        let content = "[Desktop Entry]\r\nName=App\r\n# Comment\r\n\r\n[Desktop Action new]\r\n";
+        let content = set_value(content, "Desktop Entry", "NoDisplay", "true");
+        assert_eq!(
+            content,
+            "[Desktop Entry]\r\nName=App\r\nNoDisplay=true\r\n# Comment\r\n\r\n[Desktop Action \
+             new]\r\n"
+        );
+
+        let content = set_value("[Desktop Entry]\nName=App", "Desktop Entry", "NoDisplay", "true");
+        assert_eq!(content, "[Desktop Entry]\nName=App\nNoDisplay=true");
+    }
+
+    #[test]
+    fn set_value_in_missing_group() {
+        let content = set_value("[Other]\nKey=1", "Desktop Entry", "NoDisplay", "true");
+        assert_eq!(content, "[Other]\nKey=1\n[Desktop Entry]\nNoDisplay=true\n");
+
+        let content = set_value("", "Desktop Entry", "NoDisplay", "true");
+        assert_eq!(content, "[Desktop Entry]\nNoDisplay=true\n");
+
+        let content = set_value("[Other]\r\nKey=1", "Desktop Entry", "NoDisplay", "true");
+        assert_eq!(content, "[Other]\r\nKey=1\r\n[Desktop Entry]\r\nNoDisplay=true\r\n");
+    }
+
+    #[test]
+    fn set_localized_value() {
+        let content = set_value("[A]\nName[de]=Fuh\nName=Foo\n", "A", "Name", "Bar");
+        assert_eq!(content, "[A]\nName[de]=Fuh\nName=Bar\n");
+    }
+}
@@ -0,0 +1,196 @@
+//! Desktop entry localized key matching.
+
+use std::env;
+
+/// User locale used for selecting localized values.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Locale {
+    lang: String,
+    country: Option<String>,
+    modifier: Option<String>,
+}
+
+impl Locale {
+    /// Get the locale used for translated messages.
+    ///
+    /// Returns `None` if no locale is set, or it is the `C`/`POSIX` locale.
+    pub fn from_env() -> Option<Self> {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|locale| !locale.is_empty())?;
+
+        match locale.as_str() {
+            "C" | "POSIX" => None,
+            locale => Self::parse(locale),
+        }
+    }
+
+    /// Parse a locale in the `lang_COUNTRY.ENCODING@MODIFIER` format.
+    ///
+    /// The encoding is ignored, since desktop entries are always UTF-8.
+    pub fn parse(locale: &str) -> Option<Self> {
+        let (locale, modifier) = match locale.split_once('@') {
+            Some((locale, modifier)) => (locale, Some(modifier.to_owned())),
+            None => (locale, None),
+        };
+
+        let locale = locale.split_once('.').map_or(locale, |(locale, _encoding)| locale);
+
+        let (lang, country) = match locale.split_once('_') {
+            Some((lang, country)) => (lang, Some(country.to_owned())),
+            None => (locale, None),
+        };
+
+        if lang.is_empty() {
+            return None;
+        }
+
+        Some(Self { lang: lang.to_owned(), country, modifier })
+    }
+
+    /// Get the match quality of a localized key's locale.
+    ///
+    /// Higher values indicate a better match, with unlocalized keys always
+    /// matching with the lowest quality. Returns `None` if the key's locale
+    /// must not be used for this locale.
+    fn rank(&self, key_locale: &str) -> Option<u8> {
+        let key_locale = Self::parse(key_locale)?;
+        if key_locale.lang != self.lang {
+            return None;
+        }
+
+        let country = key_locale.country.is_some();
+        let modifier = key_locale.modifier.is_some();
+        if (country && key_locale.country != self.country)
+            || (modifier && key_locale.modifier != self.modifier)
+        {
+            return None;
+        }
+
+        // Follow the spec's `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`,
+        // `lang@MODIFIER`, `lang` order.
+        match (country, modifier) {
+            (true, true) => Some(4),
+            (true, false) => Some(3),
+            (false, true) => Some(2),
+            (false, false) => Some(1),
+        }
+    }
+}
+
+/// Best matching value for a localized key.
+#[derive(Default, Debug)]
+pub struct LocaleString {
+    value: Option<String>,
+    rank: u8,
+}
+
+impl LocaleString {
+    /// Offer a new value for this key.
+    ///
+    /// The value is only stored if its locale is a better match for the user's
+    /// locale than the current one.
+    pub fn offer(&mut self, locale: Option<&Locale>, key_locale: Option<&str>, value: &str) {
+        let rank = match (locale, key_locale) {
+            (_, None) => 0,
+            (Some(locale), Some(key_locale)) => match locale.rank(key_locale) {
+                Some(rank) => rank,
+                None => return,
+            },
+            (None, Some(_)) => return,
+        };
+
+        if self.value.is_none() || rank > self.rank {
+            self.value = Some(value.to_owned());
+            self.rank = rank;
+        }
+    }
+
+    /// Get the best matching value.
+    pub fn into_value(self) -> Option<String> {
+        self.value
+    }
+}
+
+/// Split a key into its name and locale.
+///
+/// ```text
+/// Name[de_DE] => ("Name", Some("de_DE"))
+/// Name        => ("Name", None)
+/// ```
+pub fn split_key(key: &str) -> (&str, Option<&str>) {
+    match key.strip_suffix(']').and_then(|key| key.split_once('[')) {
+        Some((key, locale)) => (key, Some(locale)),
+        None => (key, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn best(locale: &str, keys: &[(Option<&str>, &str)]) -> Option<String> {
+        let locale = Locale::parse(locale);
+
+        let mut string = LocaleString::default();
+        for (key_locale, value) in keys {
+            string.offer(locale.as_ref(), *key_locale, value);
+        }
+        string.into_value()
+    }
+
+    #[test]
+    fn parse_locale() {
+        let locale = Locale::parse("sr_YU.UTF-8@Latn").unwrap();
+        assert_eq!(locale.lang, "sr");
+        assert_eq!(locale.country.as_deref(), Some("YU"));
+        assert_eq!(locale.modifier.as_deref(), Some("Latn"));
+
+        let locale = Locale::parse("de.UTF-8").unwrap();
+        assert_eq!(locale.lang, "de");
+        assert_eq!(locale.country, None);
+        assert_eq!(locale.modifier, None);
+    }
+
+    #[test]
+    fn split_keys() {
+        assert_eq!(split_key("Name[de_DE]"), ("Name", Some("de_DE")));
+        assert_eq!(split_key("Name[sr@Latn]"), ("Name", Some("sr@Latn")));
+        assert_eq!(split_key("Name"), ("Name", None));
+    }
+
+    #[test]
+    fn match_order() {
+        let keys = [
+            (None, "default"),
+            (Some("sr"), "lang"),
+            (Some("sr@Latn"), "lang@MODIFIER"),
+            (Some("sr_YU"), "lang_COUNTRY"),
+            (Some("sr_YU@Latn"), "lang_COUNTRY@MODIFIER"),
+        ];
+
+        assert_eq!(best("sr_YU@Latn", &keys).as_deref(), Some("lang_COUNTRY@MODIFIER"));
+        assert_eq!(best("sr_YU@Latn", &keys[..4]).as_deref(), Some("lang_COUNTRY"));
+        assert_eq!(best("sr_YU@Latn", &keys[..3]).as_deref(), Some("lang@MODIFIER"));
+        assert_eq!(best("sr_YU@Latn", &keys[..2]).as_deref(), Some("lang"));
+        assert_eq!(best("sr_YU@Latn", &keys[..1]).as_deref(), Some("default"));
+
+        assert_eq!(best("sr_YU", &keys).as_deref(), Some("lang_COUNTRY"));
+        assert_eq!(best("sr@Latn", &keys).as_deref(), Some("lang@MODIFIER"));
+        assert_eq!(best("sr", &keys).as_deref(), Some("lang"));
+    }
+
+    #[test]
+    fn order_independent() {
+        let keys = [(Some("de_DE"), "de_DE"), (Some("de"), "de"), (None, "default")];
+        assert_eq!(best("de_DE.UTF-8", &keys).as_deref(), Some("de_DE"));
+    }
+
+    #[test]
+    fn no_match() {
+        let keys = [(None, "default"), (Some("de_AT"), "de_AT"), (Some("fr"), "fr")];
+        assert_eq!(best("de_DE", &keys).as_deref(), Some("default"));
+        assert_eq!(best("C", &keys).as_deref(), Some("default"));
+    }
+}
@@ -0,0 +1,1249 @@
+//! Enumerate installed applications.
+
+use core::cmp::Ordering;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{env, fs, io};
+
+use tracing::error;
+use xdg::BaseDirectories;
+
+use crate::Error;
+use crate::config::{App, Apps};
+use crate::xdg::exec::FieldCodes;
+use crate::xdg::icon_theme::IconTheme;
+use crate::xdg::key_file::{Group, KeyFile};
+use crate::xdg::locale::Locale;
+
+mod exec;
+mod icon_cache;
+mod icon_theme;
+mod key_file;
+mod locale;
+
+/// Placeholder icon SVG.
+const PLACEHOLDER_SVG: &[u8] = include_bytes!("../../svgs/placeholder.svg");
+/// Poweroff entry icon SVG.
+const POWEROFF_SVG: &[u8] = include_bytes!("../../svgs/poweroff.svg");
+/// Config entry icon SVG.
+const CONFIG_SVG: &[u8] = include_bytes!("../../svgs/config.svg");
+/// Reboot entry icon SVG.
+const REBOOT_SVG: &[u8] = include_bytes!("../../svgs/reboot.svg");
+/// Hidden entry icon SVG.
+const HIDDEN_SVG: &[u8] = include_bytes!("../../svgs/hidden.svg");
+/// Icon SVG for hidden entries which cannot be toggled.
+const UNAVAILABLE_SVG: &[u8] = include_bytes!("../../svgs/unavailable.svg");
+
+#[derive(Debug)]
+pub struct DesktopEntries {
+    entries: Vec<DesktopEntry>,
+    loader: IconLoader,
+}
+
+impl DesktopEntries {
+    /// Get icons for all installed applications.
+    ///
+    /// Without an `icon_theme`, the theme configured for GTK or KDE is used.
+    pub fn new(icon_theme: Option<&str>) -> Result<Self, Error> {
+        // Get all directories containing desktop files.
+        let base_dirs = BaseDirectories::new();
+        let user_dirs = base_dirs.get_data_home();
+        let dirs = base_dirs.get_data_dirs();
+
+        // Initialize icon loader.
+        let loader = IconLoader::new(icon_theme);
+
+        // Get locale for translated names.
+        let locale = Locale::from_env();
+
+        // Configure builtin icons.
+        let entries = vec![
+            DesktopEntry {
+                name: Arc::new("Poweroff".into()),
+                exec: ExecAction::Poweroff,
+                ..Default::default()
+            },
+            DesktopEntry {
+                name: Arc::new("Tap App".into()),
+                exec: ExecAction::Config,
+                ..Default::default()
+            },
+            DesktopEntry {
+                name: Arc::new("Reboot".into()),
+                exec: ExecAction::Reboot,
+                ..Default::default()
+            },
+        ];
+
+        let mut desktop_entries = DesktopEntries { entries, loader };
+
+        // Find all desktop files in these directories, then look for their icons and
+        // executables.
+        //
+        // Entries are keyed by their desktop file ID, so files in directories of higher
+        // priority replace the ones with lower priority.
+        let mut entries: HashMap<OsString, DesktopEntry> = HashMap::new();
+        for apps_dir in dirs.iter().rev().chain(&user_dirs).map(|d| d.join("applications")) {
+            for (id, path) in desktop_files(&apps_dir) {
+                let key_file = match KeyFile::load(&path) {
+                    Ok(key_file) => key_file,
+                    Err(_) => continue,
+                };
+
+                // Apply `NoDisplay` overrides without a group, which were used by previous
+                // versions to hide applications.
+                let group = match key_file.group("Desktop Entry") {
+                    Some(group) => group,
+                    None => {
+                        let no_display = key_file.group("").and_then(|g| g.boolean("NoDisplay"));
+                        if let (Some(entry), Some(no_display)) = (entries.get_mut(&id), no_display)
+                        {
+                            entry.no_display = no_display;
+                        }
+                        continue;
+                    },
+                };
+
+                // Remove deleted entries, including ones from directories with lower priority.
+                if group.boolean("Hidden") == Some(true) {
+                    entries.remove(&id);
+                    continue;
+                }
+
+                // Ignore hidden applications, unless they override a visible entry.
+                let no_display = group.boolean("NoDisplay").unwrap_or_default();
+                if no_display && !entries.contains_key(&id) {
+                    continue;
+                }
+
+                match DesktopEntry::parse(id.clone(), path, &key_file, group, locale.as_ref()) {
+                    Some(entry) => {
+                        entries.insert(id, entry);
+                    },
+                    None => {
+                        entries.remove(&id);
+                    },
+                }
+            }
+        }
+        desktop_entries.entries.extend(entries.into_values());
+
+        // Check which applications are installed.
+        desktop_entries.update_availability();
+
+        desktop_entries.sort();
+
+        Ok(desktop_entries)
+    }
+
+    /// Sort entries for consistent display order.
+    fn sort(&mut self) {
+        // Names are compared case-insensitively, since translations do not necessarily
+        // start with an uppercase letter.
+        //
+        // Names are also used to order entries with equal actions, to keep the order
+        // total.
+        self.entries.sort_unstable_by(|first, second| {
+            first
+                .exec
+                .partial_cmp(&second.exec)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| first.name.to_lowercase().cmp(&second.name.to_lowercase()))
+        });
+    }
+
+    /// Get icon for a dekstop entry.
+    ///
+    /// The icon `size` is in logical pixels, with `scale` being the integer
+    /// scale factor it will be rendered at.
+    pub fn icon(&self, entry: &DesktopEntry, size: u32, scale: u32) -> Icon {
+        self.icon_internal(entry, size, scale).unwrap_or(Icon::new(IconIdentifier::Placeholder))
+    }
+
+    /// Attempt to load an icon.
+    ///
+    /// If no icon can be found, `None` will be returned and the placeholder
+    /// icon should be used instead.
+    fn icon_internal(&self, entry: &DesktopEntry, size: u32, scale: u32) -> Option<Icon> {
+        // Handle builtin icons.
+        match (!entry.visible(), &entry.exec) {
+            (false, ExecAction::Poweroff) => return Some(Icon::new(IconIdentifier::Poweroff)),
+            (false, ExecAction::Config) => return Some(Icon::new(IconIdentifier::Config)),
+            (false, ExecAction::Reboot) => return Some(Icon::new(IconIdentifier::Reboot)),
+            (true, _) if !entry.toggleable() => {
+                return Some(Icon::new(IconIdentifier::Unavailable));
+            },
+            (true, _) => return Some(Icon::new(IconIdentifier::Hidden)),
+            _ => (),
+        }
+
+        // Fall back to icons named after the desktop file ID or window class.
+        let icon_names = entry.icon_name.as_deref().into_iter().chain(entry.app_id());
+        let icon_names = icon_names.chain(entry.startup_wm_class.as_deref());
+
+        // Try all exact matches, before using more generic icons.
+        let mut exact_names = icon_names.clone();
+        let mut generic_names = icon_names;
+        exact_names
+            .find_map(|icon_name| self.named_icon(icon_name, size, scale))
+            .or_else(|| generic_names.find_map(|name| self.generic_icon(name, size, scale)))
+    }
+
+    /// Get icon for a desktop entry's action.
+    ///
+    /// Actions without an icon use the icon of their desktop entry.
+    pub fn action_icon(
+        &self,
+        entry: &DesktopEntry,
+        action: &DesktopAction,
+        size: u32,
+        scale: u32,
+    ) -> Icon {
+        let icon_name = action.icon_name.as_ref().or(entry.icon_name.as_ref());
+        icon_name
+            .and_then(|icon_name| {
+                self.named_icon(icon_name, size, scale)
+                    .or_else(|| self.generic_icon(icon_name, size, scale))
+            })
+            .unwrap_or(Icon::new(IconIdentifier::Placeholder))
+    }
+
+    /// Attempt to load an icon from its `Icon` key value.
+    fn named_icon(&self, icon_name: &str, size: u32, scale: u32) -> Option<Icon> {
+        // Resolve icon from name if it is not an absolute path.
+        let mut path = PathBuf::from(icon_name);
+        if !path.is_absolute() {
+            path = self.loader.icon_path(icon_name, size, scale)?;
+        }
+
+        Some(Icon::from_path(path))
+    }
+
+    /// Attempt to load an icon using the spec's generic fallbacks.
+    ///
+    /// Fallbacks are found by removing dash-separated components from the end
+    /// of the icon name, one at a time.
+    fn generic_icon(&self, icon_name: &str, size: u32, scale: u32) -> Option<Icon> {
+        if Path::new(icon_name).is_absolute() {
+            return None;
+        }
+
+        let mut fallbacks = generic_fallbacks(icon_name).into_iter();
+        let path = fallbacks.find_map(|fallback| self.loader.icon_path(&fallback, size, scale))?;
+
+        Some(Icon::from_path(path))
+    }
+
+    /// Create an iterator over all enabled applications.
+    pub fn visible(&self) -> impl Iterator<Item = &DesktopEntry> {
+        self.entries.iter().filter(|entry| entry.visible())
+    }
+
+    /// Update the installation status of all applications.
+    ///
+    /// Entries are considered unavailable if either their `TryExec` or the
+    /// `Exec` binary cannot be found.
+    fn update_availability(&mut self) {
+        let path = env::var_os("PATH").unwrap_or_default();
+        let path_dirs: Vec<_> = env::split_paths(&path).collect();
+
+        for entry in &mut self.entries {
+            let args = match &entry.exec {
+                ExecAction::Run(args) => args,
+                _ => continue,
+            };
+
+            let try_exec = entry.try_exec.as_ref();
+            let try_exec_found =
+                try_exec.is_none_or(|try_exec| is_executable(&path_dirs, try_exec));
+            let exec_found = args.as_ref().is_none_or(|args| is_executable(&path_dirs, &args[0]));
+            entry.unavailable = !try_exec_found || !exec_found;
+        }
+    }
+
+    /// Change the icon theme.
+    ///
+    /// Without an `icon_theme`, the theme configured for GTK or KDE is used.
+    pub fn set_icon_theme(&mut self, icon_theme: Option<&str>) {
+        self.loader = IconLoader::new(icon_theme);
+    }
+
+    /// Update the desktop environments used for `OnlyShowIn`/`NotShowIn`.
+    ///
+    /// The `current_desktop` uses the same colon-separated format as
+    /// `$XDG_CURRENT_DESKTOP`, which is used if no override is provided.
+    pub fn set_current_desktop(&mut self, current_desktop: Option<&str>) {
+        let current_desktop = match current_desktop {
+            Some(current_desktop) => current_desktop.to_owned(),
+            None => env::var("XDG_CURRENT_DESKTOP").unwrap_or_default(),
+        };
+        let desktops: Vec<_> = current_desktop.split(':').filter(|d| !d.is_empty()).collect();
+
+        for entry in &mut self.entries {
+            entry.excluded = !entry.shown_in(&desktops);
+        }
+    }
+
+    /// Apply the per-application config overrides.
+    ///
+    /// Entries without overrides are reset to their desktop file's values.
+    pub fn set_overrides(&mut self, apps: &Apps) {
+        for entry in &mut self.entries {
+            // Builtin entries have no desktop file ID.
+            if entry.id.is_empty() {
+                continue;
+            }
+
+            let app = entry.id.to_str().and_then(|id| apps.get(id));
+            entry.set_override(app);
+        }
+
+        // Restore sort order, since names might have changed.
+        self.sort();
+    }
+
+    /// Get immutable access to all desktop entries.
+    pub fn all(&self) -> &[DesktopEntry] {
+        &self.entries
+    }
+
+    /// Get mutable access to all desktop entries.
+    pub fn all_mut(&mut self) -> &mut [DesktopEntry] {
+        &mut self.entries
+    }
+
+    /// Get reference to index out of all visible and hidden applications.
+    pub fn all_get(&self, index: usize) -> Option<&DesktopEntry> {
+        self.entries.get(index)
+    }
+
+    /// Get mutable reference to index out of all visible and hidden
+    /// applications.
+    pub fn all_get_mut(&mut self, index: usize) -> Option<&mut DesktopEntry> {
+        self.entries.get_mut(index)
+    }
+
+    /// Get total number of visible and hidden applications
+    pub fn all_len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Remove a desktop entry.
+    pub fn remove(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+        }
+    }
+}
+
+/// Desktop entry information.
+#[derive(Default, Debug)]
+pub struct DesktopEntry {
+    pub icon_name: Option<String>,
+    pub name: Arc<String>,
+    pub exec: ExecAction,
+    pub terminal: bool,
+    pub startup_notify: bool,
+    pub working_directory: Option<PathBuf>,
+    pub prefers_non_default_gpu: bool,
+    pub actions: Vec<DesktopAction>,
+
+    // Grid index cache used during rendering.
+    pub grid_index: Option<usize>,
+
+    only_show_in: Vec<String>,
+    not_show_in: Vec<String>,
+    startup_wm_class: Option<String>,
+    dbus_activatable: bool,
+    try_exec: Option<String>,
+    no_display: bool,
+    hidden_override: Option<bool>,
+    path: PathBuf,
+    id: OsString,
+    excluded: bool,
+    unavailable: bool,
+
+    // Desktop file values, before applying config overrides.
+    desktop_name: Arc<String>,
+    desktop_icon_name: Option<String>,
+}
+
+impl DesktopEntry {
+    /// Parse the `Desktop Entry` group of a desktop file.
+    ///
+    /// Returns `None` if the entry is not an application or link, or if a
+    /// required key is missing or invalid.
+    fn parse(
+        id: OsString,
+        path: PathBuf,
+        key_file: &KeyFile,
+        group: &Group,
+        locale: Option<&Locale>,
+    ) -> Option<Self> {
+        // Find name, icon, and executable for the desktop entry.
+        let name = group.locale_string("Name", locale)?;
+        let icon_name = group.string("Icon");
+        let dbus_activatable = group.boolean("DBusActivatable").unwrap_or_default();
+
+        // Get the action for launching the entry.
+        let (entry_type, exec, url) =
+            (group.string("Type"), group.string("Exec"), group.string("URL"));
+        let exec = match (entry_type.as_deref(), exec, url) {
+            (Some("Link"), _, Some(url)) => ExecAction::OpenUrl(url),
+            (None | Some("Application"), Some(exec), _) => {
+                // Split command line into its arguments.
+                let fields = FieldCodes { icon: icon_name.as_deref(), name: &name, path: &path };
+                let args = exec::parse(&exec, fields);
+                if args.is_none() {
+                    error!("Invalid Exec key in {path:?}: {exec:?}");
+                }
+                ExecAction::Run(Some(args?))
+            },
+            // `Exec` is optional for applications launched over DBus.
+            (None | Some("Application"), None, _) if dbus_activatable => ExecAction::Run(None),
+            // Ignore directories and non-executable desktop files.
+            _ => return None,
+        };
+
+        // Resolve all actions listed in the `Actions` key.
+        let actions = group
+            .list("Actions", ';')
+            .iter()
+            .filter_map(|action_id| {
+                let group = key_file.group(&format!("Desktop Action {action_id}"))?;
+                let icon_name = icon_name.as_deref();
+                let action = DesktopAction::new(
+                    action_id,
+                    group,
+                    locale,
+                    &path,
+                    icon_name,
+                    dbus_activatable,
+                );
+                if action.is_none() {
+                    error!("Invalid desktop action {action_id:?} in {path:?}");
+                }
+                action
+            })
+            .collect();
+
+        let name = Arc::new(name);
+        Some(Self {
+            desktop_name: name.clone(),
+            desktop_icon_name: icon_name.clone(),
+            icon_name,
+            actions,
+            only_show_in: group.list("OnlyShowIn", ';'),
+            not_show_in: group.list("NotShowIn", ';'),
+            startup_wm_class: group.string("StartupWMClass"),
+            terminal: group.boolean("Terminal").unwrap_or_default(),
+            working_directory: group
+                .string("Path")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+            prefers_non_default_gpu: group.boolean("PrefersNonDefaultGPU").unwrap_or_default(),
+            startup_notify: group.boolean("StartupNotify").unwrap_or_default(),
+            try_exec: group.string("TryExec"),
+            no_display: group.boolean("NoDisplay").unwrap_or_default(),
+            dbus_activatable,
+            exec,
+            path,
+            id,
+            name,
+            hidden_override: Default::default(),
+            grid_index: Default::default(),
+            excluded: Default::default(),
+            unavailable: Default::default(),
+        })
+    }
+
+    /// Toggle the hidden status of the desktop entry.
+    pub fn toggle_hidden(&mut self) -> io::Result<()> {
+        self.set_no_display(!self.no_display)
+    }
+
+    /// Update the `NoDisplay` key of the desktop entry.
+    ///
+    /// Changes are always written to the user's data home, copying the entry's
+    /// desktop file there if necessary.
+    fn set_no_display(&mut self, no_display: bool) -> io::Result<()> {
+        // Get path of the `~/.local/share/applications` directory.
+        let data_home = match BaseDirectories::new().get_data_home() {
+            Some(data_home) => data_home,
+            None => return Err(io::Error::other("missing user data home")),
+        };
+        let apps_dir = data_home.join("applications");
+
+        // Ensure directory exists.
+        fs::create_dir_all(&apps_dir)?;
+
+        // Update the desktop file the entry was loaded from, retaining all other keys.
+        let content = fs::read_to_string(&self.path)?;
+        let value = if no_display { "true" } else { "false" };
+        let content = key_file::set_value(&content, "Desktop Entry", "NoDisplay", value);
+
+        // Replace symlinks, instead of writing to their target.
+        let file_path = user_desktop_file(&apps_dir, &self.id);
+        if fs::symlink_metadata(&file_path).is_ok_and(|metadata| metadata.is_symlink()) {
+            fs::remove_file(&file_path)?;
+        }
+        fs::write(&file_path, &content)?;
+
+        self.no_display = no_display;
+        self.set_path(file_path, &content);
+
+        Ok(())
+    }
+
+    /// Update the location of the entry's desktop file.
+    ///
+    /// The command lines are parsed again from `content`, to expand `%k` with
+    /// the new location.
+    fn set_path(&mut self, path: PathBuf, content: &str) {
+        let key_file = KeyFile::parse(content, &path);
+        let locale = Locale::from_env();
+        let entry = key_file.group("Desktop Entry").and_then(|group| {
+            Self::parse(self.id.clone(), path.clone(), &key_file, group, locale.as_ref())
+        });
+        if let Some(entry) = entry {
+            self.exec = entry.exec;
+            self.actions = entry.actions;
+        }
+
+        self.path = path;
+    }
+
+    /// Get the desktop file ID.
+    ///
+    /// Returns `None` for builtin entries.
+    pub fn desktop_id(&self) -> Option<&str> {
+        self.id.to_str().filter(|id| !id.is_empty())
+    }
+
+    /// Get the application ID, which is the desktop file ID without its
+    /// `.desktop` suffix.
+    pub fn app_id(&self) -> Option<&str> {
+        self.id.to_str()?.strip_suffix(".desktop")
+    }
+
+    /// Get the application ID, if the application should be launched over
+    /// DBus.
+    pub fn dbus_app_id(&self) -> Option<&str> {
+        self.app_id().filter(|_| self.dbus_activatable)
+    }
+
+    /// Check whether the desktop entry is marked as `NoDisplay`.
+    ///
+    /// This can be overridden in the config.
+    pub fn hidden(&self) -> bool {
+        self.hidden_override.unwrap_or(self.no_display)
+    }
+
+    /// Apply config overrides to this entry.
+    fn set_override(&mut self, app: Option<&App>) {
+        let app = app.map(|app| (app.name.as_ref(), app.icon.as_ref(), app.hidden));
+        let (name, icon, hidden) = app.unwrap_or_default();
+
+        self.name = match name {
+            Some(name) => Arc::new(name.clone()),
+            None => self.desktop_name.clone(),
+        };
+        self.icon_name = icon.or(self.desktop_icon_name.as_ref()).cloned();
+        self.hidden_override = hidden;
+    }
+
+    /// Check whether the entry's visibility can be toggled by the user.
+    ///
+    /// Entries excluded by `OnlyShowIn`/`NotShowIn` or with missing binaries
+    /// stay hidden regardless of their `NoDisplay` key, while the config's
+    /// `hidden` override always takes precedence over it.
+    pub fn toggleable(&self) -> bool {
+        !self.excluded && !self.unavailable && self.hidden_override.is_none()
+    }
+
+    /// Check whether the desktop entry should be shown outside of
+    /// configuration mode.
+    pub fn visible(&self) -> bool {
+        !self.hidden() && !self.excluded && !self.unavailable
+    }
+
+    /// Check if the entry should be shown in the specified desktop
+    /// environments.
+    ///
+    /// Desktops are checked in order, with the first one present in either
+    /// `OnlyShowIn` or `NotShowIn` deciding the visibility.
+    fn shown_in(&self, desktops: &[&str]) -> bool {
+        for desktop in desktops {
+            if self.only_show_in.iter().any(|d| d == desktop) {
+                return true;
+            } else if self.not_show_in.iter().any(|d| d == desktop) {
+                return false;
+            }
+        }
+
+        // Hide entries restricted to other desktops by default.
+        self.only_show_in.is_empty()
+    }
+}
+
+/// Additional application action.
+#[derive(Debug)]
+pub struct DesktopAction {
+    /// Action identifier from the desktop entry's `Actions` key.
+    pub id: String,
+    pub icon_name: Option<String>,
+    pub name: Arc<String>,
+    /// Command line arguments, like [`ExecAction::Run`].
+    pub exec: Option<Vec<String>>,
+}
+
+impl DesktopAction {
+    /// Create an action from its `Desktop Action` group.
+    ///
+    /// Returns `None` if a required key is missing or invalid.
+    fn new(
+        id: &str,
+        group: &Group,
+        locale: Option<&Locale>,
+        path: &Path,
+        entry_icon: Option<&str>,
+        dbus_activatable: bool,
+    ) -> Option<Self> {
+        let name = group.locale_string("Name", locale)?;
+        let icon_name = group.string("Icon");
+
+        let icon = icon_name.as_deref().or(entry_icon);
+        let fields = FieldCodes { icon, name: &name, path };
+        let exec = match group.string("Exec") {
+            Some(exec) => Some(exec::parse(&exec, fields)?),
+            None if dbus_activatable => None,
+            None => return None,
+        };
+
+        Some(Self { exec, icon_name, id: id.into(), name: Arc::new(name) })
+    }
+}
+
+/// Desktop entry icon data.
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+pub struct Icon {
+    identifier: IconIdentifier,
+    symbolic: bool,
+}
+
+impl Icon {
+    /// Create a new builtin icon.
+    fn new(identifier: IconIdentifier) -> Self {
+        Self { identifier, symbolic: false }
+    }
+
+    /// Create an icon from an image file.
+    pub fn from_path(path: PathBuf) -> Self {
+        // Symbolic icons need to be recolored to match the foreground color.
+        let file_stem = path.file_stem().and_then(|stem| stem.to_str());
+        let symbolic = file_stem.is_some_and(|stem| stem.ends_with("-symbolic"));
+
+        Self { identifier: IconIdentifier::Path(path), symbolic }
+    }
+
+    /// Load the data associated with this icon.
+    ///
+    /// The icon format is detected from the data itself, since icon file
+    /// extensions are not reliable.
+    pub fn load(&self) -> (Cow<'static, [u8]>, IconType) {
+        let data = match &self.identifier {
+            IconIdentifier::Path(path) => match read_to_vec(path) {
+                Ok(data) => match IconType::detect(&data) {
+                    Some(icon_type) => return (Cow::Owned(data), icon_type),
+                    None => {
+                        error!("Unsupported icon format: {path:?}");
+                        PLACEHOLDER_SVG
+                    },
+                },
+                Err(err) => {
+                    error!("Failed to read icon {path:?}: {err}");
+                    PLACEHOLDER_SVG
+                },
+            },
+            IconIdentifier::Placeholder => PLACEHOLDER_SVG,
+            IconIdentifier::Poweroff => POWEROFF_SVG,
+            IconIdentifier::Config => CONFIG_SVG,
+            IconIdentifier::Reboot => REBOOT_SVG,
+            IconIdentifier::Hidden => HIDDEN_SVG,
+            IconIdentifier::Unavailable => UNAVAILABLE_SVG,
+        };
+
+        (Cow::Borrowed(data), IconType::Svg)
+    }
+
+    /// Check if this is a single-color symbolic icon.
+    pub fn symbolic(&self) -> bool {
+        self.symbolic
+    }
+}
+
+/// Type of desktop entry icons.
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
+pub enum IconType {
+    Svg,
+    /// Gzip-compressed SVG.
+    Svgz,
+    /// X PixMap.
+    Xpm,
+    /// Raster image supported by Skia, like PNG, JPEG, GIF or WebP.
+    Raster,
+}
+
+impl IconType {
+    /// Detect the icon type from its file signature.
+    fn detect(data: &[u8]) -> Option<Self> {
+        const RASTER_SIGNATURES: [&[u8]; 4] =
+            [b"\x89PNG\r\n\x1a\n", b"\xff\xd8\xff", b"GIF87a", b"GIF89a"];
+
+        if RASTER_SIGNATURES.iter().any(|signature| data.starts_with(signature))
+            || (data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP"))
+        {
+            return Some(Self::Raster);
+        }
+
+        if data.starts_with(b"\x1f\x8b") {
+            return Some(Self::Svgz);
+        }
+
+        // Skip leading whitespace and UTF-8 BOM of text formats.
+        let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+        let data = data.trim_ascii_start();
+
+        if data.starts_with(b"/* XPM */") {
+            Some(Self::Xpm)
+        } else if data.starts_with(b"<") && data.windows(4).any(|window| window == b"<svg") {
+            Some(Self::Svg)
+        } else {
+            None
+        }
+    }
+}
+
+/// Unique desktop entry icon identifier.
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+enum IconIdentifier {
+    Path(PathBuf),
+    Placeholder,
+    Poweroff,
+    Config,
+    Reboot,
+    Hidden,
+    Unavailable,
+}
+
+/// Simple loader for app icons.
+#[derive(Debug)]
+struct IconLoader {
+    /// Icon themes in order of lookup priority.
+    themes: Vec<IconTheme>,
+    /// Directories with fallback icons outside of any theme.
+    pixmaps_dirs: Vec<PathBuf>,
+    /// Previously resolved icon paths.
+    cache: Mutex<HashMap<(String, u32, u32), Option<PathBuf>>>,
+}
+
+impl IconLoader {
+    /// Initialize the icon loader.
+    ///
+    /// This only loads the theme metadata, icons are resolved on demand
+    /// instead.
+    fn new(theme: Option<&str>) -> Self {
+        // Get icon directories in descending priority, with user directories first.
+        let dirs = BaseDirectories::new();
+        let data_dirs = dirs.get_data_dirs();
+        let home_icons = env::var_os("HOME").map(|home| PathBuf::from(home).join(".icons"));
+        let user_icons = dirs.get_data_home().map(|data_home| data_home.join("icons"));
+        let base_dirs: Vec<_> = home_icons
+            .into_iter()
+            .chain(user_icons)
+            .chain(data_dirs.iter().map(|dir| dir.join("icons")))
+            .collect();
+
+        // Fall back to the desktop's icon theme if none was configured.
+        let system_theme = theme.is_none().then(icon_theme::system_theme).flatten();
+        let theme = theme.or(system_theme.as_deref()).unwrap_or("hicolor");
+
+        // Load the theme and all its parents, in the spec's depth-first lookup order.
+        //
+        // The `hicolor` theme is always searched last, even if it is not inherited.
+        let mut themes = Vec::new();
+        let mut theme_names = Vec::new();
+        load_theme(&base_dirs, theme, &mut themes, &mut theme_names);
+        load_theme(&base_dirs, "hicolor", &mut themes, &mut theme_names);
+
+        let pixmaps_dirs = data_dirs.iter().map(|dir| dir.join("pixmaps")).collect();
+
+        Self { themes, pixmaps_dirs, cache: Default::default() }
+    }
+
+    /// Get the ideal icon for a specific size and scale.
+    fn icon_path(&self, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let key = (icon.to_owned(), size, scale);
+        if let Some(path) = self.cache.lock().unwrap().get(&key) {
+            return path.clone();
+        }
+
+        // Use symbolic icons as a last resort, since they lack color.
+        let path = self
+            .lookup(icon, size, scale)
+            .or_else(|| self.lookup(&format!("{icon}-symbolic"), size, scale));
+
+        self.cache.lock().unwrap().insert(key, path.clone());
+
+        path
+    }
+
+    /// Find an icon in all themes, falling back to the pixmaps directories.
+    fn lookup(&self, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        if let Some(path) = self.themes.iter().find_map(|theme| theme.lookup(icon, size, scale)) {
+            return Some(path);
+        }
+
+        // Prefer SVGs, since we don’t have any information about the size of pixmaps.
+        self.pixmaps_dirs.iter().find_map(|dir| {
+            let extensions = ["svg", "svgz", "png", "xpm", "jpg", "jpeg", "gif", "webp"].iter();
+            let mut files = extensions.map(|ext| dir.join(format!("{icon}.{ext}")));
+            files.find(|file| file.is_file())
+        })
+    }
+}
+
+/// Get the generic fallbacks for an icon name, from most to least specific.
+///
+/// The `-symbolic` suffix is retained, to avoid falling back to colored icons.
+///
+/// ```text
+/// libreoffice-writer-beta => ["libreoffice-writer", "libreoffice"]
+/// media-eject-cd-symbolic => ["media-eject-symbolic", "media-symbolic"]
+/// ```
+fn generic_fallbacks(icon_name: &str) -> Vec<String> {
+    let (mut name, suffix) = match icon_name.strip_suffix("-symbolic") {
+        Some(name) => (name, "-symbolic"),
+        None => (icon_name, ""),
+    };
+
+    let mut fallbacks = Vec::new();
+    while let Some((prefix, _)) = name.rsplit_once('-') {
+        name = prefix;
+        if !name.is_empty() {
+            fallbacks.push(format!("{name}{suffix}"));
+        }
+    }
+    fallbacks
+}
+
+/// Recursively load an icon theme and the themes it inherits from.
+fn load_theme(
+    base_dirs: &[PathBuf],
+    name: &str,
+    themes: &mut Vec<IconTheme>,
+    theme_names: &mut Vec<String>,
+) {
+    // Skip themes which were already loaded, to avoid redundant work and cycles.
+    if theme_names.iter().any(|theme_name| theme_name == name) {
+        return;
+    }
+    theme_names.push(name.into());
+
+    let theme = match IconTheme::load(base_dirs, name) {
+        Some(theme) => theme,
+        None => return,
+    };
+    let parents = theme.inherits.clone();
+    themes.push(theme);
+
+    for parent in &parents {
+        load_theme(base_dirs, parent, themes, theme_names);
+    }
+}
+
+/// Launcher action.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub enum ExecAction {
+    #[default]
+    Poweroff,
+    Config,
+    Reboot,
+    /// Command line arguments, guaranteed to contain at least one element.
+    ///
+    /// This is `None` for DBus activatable applications without `Exec`.
+    Run(Option<Vec<String>>),
+    OpenUrl(String),
+}
+
+impl PartialOrd for ExecAction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+
+        match (self, other) {
+            (Self::Config, Self::Poweroff)
+            | (Self::Reboot, Self::Poweroff | Self::Config)
+            | (Self::Run(_) | Self::OpenUrl(_), Self::Poweroff | Self::Config | Self::Reboot) => {
+                Some(Ordering::Greater)
+            },
+            (Self::Run(_) | Self::OpenUrl(_), Self::Run(_) | Self::OpenUrl(_)) => None,
+            _ => Some(Ordering::Less),
+        }
+    }
+}
+
+/// Get the path of a desktop file ID in the user's `applications` directory.
+///
+/// Existing files are reused, even if their ID is derived from a
+/// subdirectory, to avoid creating a second file with the same ID.
+fn user_desktop_file(apps_dir: &Path, id: &OsStr) -> PathBuf {
+    let mut files = desktop_files(apps_dir).into_iter();
+    match files.find(|(file_id, _)| file_id == id) {
+        Some((_, path)) => path,
+        None => apps_dir.join(id),
+    }
+}
+
+/// Find all desktop files in an `applications` directory.
+///
+/// Returns the desktop file ID and path for every desktop file, including the
+/// ones in subdirectories. The ID is the file's path relative to `apps_dir`,
+/// with `/` replaced by `-`.
+fn desktop_files(apps_dir: &Path) -> Vec<(OsString, PathBuf)> {
+    let mut files = Vec::new();
+
+    let mut dirs = vec![(apps_dir.to_path_buf(), OsString::new())];
+    while let Some((dir, id_prefix)) = dirs.pop() {
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+
+            let mut id = id_prefix.clone();
+            id.push(entry.file_name());
+
+            // Symlinked directories are not followed, to avoid cycles.
+            if file_type.is_dir() {
+                id.push("-");
+                dirs.push((entry.path(), id));
+            } else if (file_type.is_file() || file_type.is_symlink())
+                && entry.file_name().to_string_lossy().ends_with(".desktop")
+            {
+                files.push((id, entry.path()));
+            }
+        }
+    }
+
+    files
+}
+
+/// Check if a binary is an executable file.
+///
+/// Binaries without a `/` are resolved using the `$PATH` directories.
+fn is_executable(path_dirs: &[PathBuf], binary: &str) -> bool {
+    let is_executable_file = |path: &Path| {
+        fs::metadata(path)
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    };
+
+    if binary.contains('/') {
+        is_executable_file(Path::new(binary))
+    } else {
+        path_dirs.iter().any(|dir| is_executable_file(&dir.join(binary)))
+    }
+}
+
+/// Read all the bytes in a file.
+fn read_to_vec(path: &Path) -> Result<Vec<u8>, io::Error> {
+    let mut file = File::open(path)?;
+
+    // Create a vec with its capacity matching the file size.
+    let mut data = Vec::new();
+    if let Ok(metadata) = file.metadata() {
+        data.reserve_exact(metadata.len() as usize);
+    }
+
+    file.read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Deref;
+
+    use super::*;
+
+    /// Temporary test directory, which is removed once dropped.
+    pub struct TempDir(PathBuf);
+
+    impl TempDir {
+        /// Create an empty temporary directory, unique to the test `name`.
+        pub fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("tzompantli-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn desktop_file_ids() {
+        let apps_dir = TempDir::new("ids");
+        fs::create_dir_all(apps_dir.join("kde4/vendor")).unwrap();
+        fs::write(apps_dir.join("top.desktop"), "").unwrap();
+        fs::write(apps_dir.join("kde4/app.desktop"), "").unwrap();
+        fs::write(apps_dir.join("kde4/vendor/app.desktop"), "").unwrap();
+        fs::write(apps_dir.join("kde4/readme.txt"), "").unwrap();
+
+        let mut files = desktop_files(&apps_dir);
+        files.sort_unstable();
+
+        assert_eq!(files, [
+            (OsString::from("kde4-app.desktop"), apps_dir.join("kde4/app.desktop")),
+            (OsString::from("kde4-vendor-app.desktop"), apps_dir.join("kde4/vendor/app.desktop")),
+            (OsString::from("top.desktop"), apps_dir.join("top.desktop")),
+        ]);
+    }
+
+    #[test]
+    fn user_desktop_file_path() {
+        let apps_dir = TempDir::new("user-file");
+        fs::create_dir_all(apps_dir.join("vendor")).unwrap();
+        fs::write(apps_dir.join("vendor/app.desktop"), "").unwrap();
+
+        let existing = user_desktop_file(&apps_dir, OsStr::new("vendor-app.desktop"));
+        let new = user_desktop_file(&apps_dir, OsStr::new("other.desktop"));
+
+        assert_eq!(existing, apps_dir.join("vendor/app.desktop"));
+        assert_eq!(new, apps_dir.join("other.desktop"));
+    }
+
+    #[test]
+    fn app_overrides() {
+        let name = Arc::new(String::from("Firefox"));
+        let mut entry = DesktopEntry {
+            desktop_name: name.clone(),
+            desktop_icon_name: Some("firefox".into()),
+            icon_name: Some("firefox".into()),
+            no_display: true,
+            name,
+            ..Default::default()
+        };
+
+        let app = App {
+            name: Some("Web".into()),
+            icon: Some("/tmp/web.svg".into()),
+            ..Default::default()
+        };
+        entry.set_override(Some(&app));
+        assert_eq!(entry.name.as_str(), "Web");
+        assert_eq!(entry.icon_name.as_deref(), Some("/tmp/web.svg"));
+        assert!(entry.hidden());
+
+        entry.set_override(Some(&App { hidden: Some(false), ..Default::default() }));
+        assert_eq!(entry.name.as_str(), "Firefox");
+        assert_eq!(entry.icon_name.as_deref(), Some("firefox"));
+        assert!(!entry.hidden());
+
+        entry.set_override(None);
+        assert!(entry.hidden());
+    }
+
+    #[test]
+    fn set_path() {
+        let content = [
+            "[Desktop Entry]",
+            "Name=App",
+            "Exec=app %k",
+            "Actions=new;",
+            "[Desktop Action new]",
+            "Name=New",
+            "Exec=app --new %k",
+        ];
+        let mut entry = DesktopEntry { id: "app.desktop".into(), ..Default::default() };
+
+        let path = "/home/user/app.desktop";
+        entry.set_path(path.into(), &content.join("\n"));
+
+        assert_eq!(entry.path, Path::new(path));
+        assert_eq!(entry.exec, ExecAction::Run(Some(vec!["app".into(), path.into()])));
+        assert_eq!(entry.actions[0].exec, Some(vec!["app".into(), "--new".into(), path.into()]));
+    }
+
+    #[test]
+    fn toggleable() {
+        let mut entry = DesktopEntry { no_display: true, ..Default::default() };
+        assert!(entry.toggleable());
+
+        entry.excluded = true;
+        assert!(!entry.toggleable());
+
+        entry.excluded = false;
+        entry.unavailable = true;
+        assert!(!entry.toggleable());
+
+        entry.unavailable = false;
+        entry.set_override(Some(&App { hidden: Some(false), ..Default::default() }));
+        assert!(!entry.toggleable());
+        assert!(entry.visible());
+
+        entry.set_override(None);
+        assert!(entry.toggleable());
+    }
+
+    #[test]
+    fn parse_dbus_activatable() {
+        let path = PathBuf::from("/usr/share/applications/org.example.App.desktop");
+        let parse = |content: &str| {
+            let key_file = KeyFile::parse(content, &path);
+            let group = key_file.group("Desktop Entry").unwrap();
+            DesktopEntry::parse(
+                "org.example.App.desktop".into(),
+                path.clone(),
+                &key_file,
+                group,
+                None,
+            )
+        };
+
+        let content = [
+            "[Desktop Entry]",
+            "Name=App",
+            "DBusActivatable=true",
+            "Actions=new;",
+            "[Desktop Action new]",
+            "Name=New Window",
+        ];
+        let entry = parse(&content.join("\n")).unwrap();
+        assert_eq!(entry.exec, ExecAction::Run(None));
+        assert_eq!(entry.dbus_app_id(), Some("org.example.App"));
+        assert_eq!(entry.actions.len(), 1);
+        assert_eq!(entry.actions[0].exec, None);
+
+        // `Exec` is still used as fallback, when present.
+        let entry = parse("[Desktop Entry]\nName=App\nDBusActivatable=true\nExec=app %u\n");
+        assert_eq!(entry.unwrap().exec, ExecAction::Run(Some(vec!["app".into()])));
+
+        // Applications without DBus activation require `Exec`.
+        assert!(parse("[Desktop Entry]\nName=App\n").is_none());
+        let entry = parse(
+            "[Desktop Entry]\nName=App\nExec=app\nActions=new;\n\n[Desktop Action new]\nName=New\n",
+        );
+        assert!(entry.unwrap().actions.is_empty());
+    }
+
+    #[test]
+    fn sort_equal_actions() {
+        let entry = |name: &str| DesktopEntry {
+            name: Arc::new(name.into()),
+            exec: ExecAction::Run(None),
+            ..Default::default()
+        };
+        let loader =
+            IconLoader { themes: Vec::new(), pixmaps_dirs: Vec::new(), cache: Default::default() };
+        let entries = vec![entry("beta"), DesktopEntry::default(), entry("Alpha")];
+        let mut desktop_entries = DesktopEntries { entries, loader };
+
+        desktop_entries.sort();
+
+        let names: Vec<_> = desktop_entries.all().iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["", "Alpha", "beta"]);
+    }
+
+    #[test]
+    fn dbus_app_id() {
+        let mut entry =
+            DesktopEntry { id: "org.gnome.Nautilus.desktop".into(), ..Default::default() };
+        assert_eq!(entry.dbus_app_id(), None);
+
+        entry.dbus_activatable = true;
+        assert_eq!(entry.dbus_app_id(), Some("org.gnome.Nautilus"));
+    }
+
+    #[test]
+    fn icon_generic_fallbacks() {
+        assert_eq!(generic_fallbacks("libreoffice-writer-beta"), [
+            "libreoffice-writer",
+            "libreoffice"
+        ]);
+        assert_eq!(generic_fallbacks("org.gnome.Settings-symbolic-foo"), [
+            "org.gnome.Settings-symbolic",
+            "org.gnome.Settings",
+        ]);
+        assert_eq!(generic_fallbacks("media-eject-cd-symbolic"), [
+            "media-eject-symbolic",
+            "media-symbolic",
+        ]);
+        assert_eq!(generic_fallbacks("-app--"), ["-app-", "-app"]);
+        assert!(generic_fallbacks("firefox").is_empty());
+        assert!(generic_fallbacks("app-symbolic").is_empty());
+    }
+
+    #[test]
+    fn pixmaps_lookup() {
+        let pixmaps_dir = TempDir::new("pixmaps");
+        for file in ["game.webp", "photo.jpg", "both.png", "both.svgz", "legacy.xpm"] {
+            fs::write(pixmaps_dir.join(file), "").unwrap();
+        }
+
+        let loader = IconLoader {
+            themes: Vec::new(),
+            pixmaps_dirs: vec![pixmaps_dir.to_path_buf()],
+            cache: Default::default(),
+        };
+        let lookup = |icon| loader.lookup(icon, 48, 1);
+
+        assert_eq!(lookup("game"), Some(pixmaps_dir.join("game.webp")));
+        assert_eq!(lookup("photo"), Some(pixmaps_dir.join("photo.jpg")));
+        assert_eq!(lookup("both"), Some(pixmaps_dir.join("both.svgz")));
+        assert_eq!(lookup("legacy"), Some(pixmaps_dir.join("legacy.xpm")));
+        assert_eq!(lookup("missing"), None);
+    }
+
+    #[test]
+    fn detect_icon_type() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/icons");
+        let detect = |file: &str| IconType::detect(&fs::read(fixtures.join(file)).unwrap());
+
+        assert_eq!(detect("icon.svg"), Some(IconType::Svg));
+        assert_eq!(detect("icon.svgz"), Some(IconType::Svgz));
+        assert_eq!(detect("icon.xpm"), Some(IconType::Xpm));
+        assert_eq!(detect("icon.png"), Some(IconType::Raster));
+        assert_eq!(detect("icon.jpg"), Some(IconType::Raster));
+        assert_eq!(detect("icon.gif"), Some(IconType::Raster));
+        assert_eq!(detect("icon.webp"), Some(IconType::Raster));
+    }
+
+    #[test]
+    fn detect_icon_type_by_content() {
+        assert_eq!(IconType::detect(b"\xef\xbb\xbf\n<svg/>"), Some(IconType::Svg));
+        assert_eq!(IconType::detect(b"<!-- comment -->\n<svg/>"), Some(IconType::Svg));
+        assert_eq!(IconType::detect(b"<html></html>"), None);
+        assert_eq!(IconType::detect(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(IconType::detect(b""), None);
+    }
+}